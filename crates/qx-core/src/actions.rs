@@ -1,16 +1,38 @@
+use std::collections::HashMap;
 use std::fmt::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use color_eyre::eyre::{bail, eyre};
 use itertools::Itertools;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 use url::Url;
 
 use crate::{
+    background::BackgroundTask,
+    backup::ActionBackup,
+    checklist::{parse_checklist_items, ActionChecklist, ChecklistOutcome},
     context::Context,
+    dotenv::parse_dotenv,
+    filesystem,
+    foreach::ActionForeach,
+    git::ActionGit,
+    group::{ActionDefaults, ActionGroup, FailurePolicy},
     intent::{CommandCreationType, CommandIntent},
-    resolvable::Resolvable,
-    CommandExecutor, System,
+    message::MessageLevel,
+    one_or_many::OneOrMany,
+    process::{self, ProcessMatcher},
+    require::ActionRequire,
+    resolvable::{Resolvable, ResolvableClone},
+    script::{self, ActionScript},
+    system::NotificationUrgency,
+    timer::ActionTimer,
+    tmux::ActionTmux,
+    vscode::{VSCodeRemote, VSCodeWindow},
+    watch::ActionWatch,
+    Clock, CommandExecutor, Console, FileAccess, System,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,28 +40,158 @@ pub struct ActionRun {
     pub target: PathBuf,
     pub args: Option<Vec<String>>,
     pub working_directory: Option<PathBuf>,
-    #[serde(default = "CommandCreationType::detach")]
-    pub creation_type: CommandCreationType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_type: Option<CommandCreationType>,
+    #[serde(default)]
+    pub single_instance: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionShowMessage {
     pub message: String,
+    #[serde(default)]
+    pub level: MessageLevel,
+    #[serde(default)]
+    pub pause: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionOpenUrl {
-    pub target: Url,
+    pub target: OneOrMany<String>,
+    pub browser: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionOpenFile {
-    pub target: PathBuf,
+    pub target: OneOrMany<PathBuf>,
+    pub with: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionVSCode {
+    pub target: OneOrMany<PathBuf>,
+    pub window: Option<VSCodeWindow>,
+    pub profile: Option<String>,
+    pub remote: Option<VSCodeRemote>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionNotify {
+    pub title: String,
+    pub body: String,
+    pub urgency: Option<NotificationUrgency>,
+    pub icon: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionClipboard {
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileExistsPolicy {
+    Skip,
+    #[default]
+    Overwrite,
+    Fail,
+    Append,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionWriteFile {
     pub target: PathBuf,
+    pub content: Option<String>,
+    pub template: Option<PathBuf>,
+    #[serde(default)]
+    pub if_exists: FileExistsPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionEnsureDir {
+    pub target: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionCopy {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionMove {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionSymlink {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionRemove {
+    pub target: PathBuf,
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionTerminal {
+    pub working_directory: PathBuf,
+    pub command: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionSpawnShell {
+    pub working_directory: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionEditor {
+    pub editor: String,
+    pub target: PathBuf,
+    pub line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionKillProcess {
+    pub name: Option<String>,
+    pub command_line: Option<String>,
+    pub pidfile: Option<PathBuf>,
+    #[serde(default = "ActionKillProcess::default_grace_period")]
+    pub grace_period: u64,
+}
+
+impl ActionKillProcess {
+    fn default_grace_period() -> u64 {
+        5
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionSetVariable {
+    pub name: String,
+    pub value: Option<String>,
+    pub from_file: Option<PathBuf>,
+    pub from_env: Option<String>,
+    pub first_existing: Option<Vec<PathBuf>>,
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionLoadDotenv {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub export: bool,
+    #[serde(default)]
+    pub optional: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,16 +203,121 @@ pub enum Action {
     OpenUrl(ActionOpenUrl),
     #[serde(rename = "vscode")]
     VSCode(ActionVSCode),
+    Notify(ActionNotify),
+    Clipboard(ActionClipboard),
+    WriteFile(ActionWriteFile),
+    EnsureDir(ActionEnsureDir),
+    Copy(ActionCopy),
+    Move(ActionMove),
+    Symlink(ActionSymlink),
+    Remove(ActionRemove),
+    Git(ActionGit),
+    Terminal(ActionTerminal),
+    Tmux(ActionTmux),
+    Editor(ActionEditor),
+    KillProcess(ActionKillProcess),
+    SetVariable(ActionSetVariable),
+    LoadDotenv(ActionLoadDotenv),
+    Timer(ActionTimer),
+    Checklist(ActionChecklist),
+    Script(ActionScript),
+    Require(ActionRequire),
+    SpawnShell(ActionSpawnShell),
+    Watch(ActionWatch),
+    Foreach(ActionForeach),
+    Group(ActionGroup),
+    Backup(ActionBackup),
 }
 
-pub struct ActionContext<'a, E: CommandExecutor> {
+pub struct ActionContext<'a, E: CommandExecutor, F: FileAccess, C: Console, K: Clock> {
     pub system: &'a System,
-    pub context: &'a Context,
+    pub context: Context,
     pub executor: &'a E,
+    pub file_access: &'a F,
+    pub console: &'a C,
+    pub clock: &'a K,
+    pub environment: Option<String>,
+    pub exported_variables: HashMap<String, String>,
+    /// Values loaded from dotenv files, exported or not.
+    pub dotenv_variables: HashMap<String, String>,
+    /// Working directory of the environment, its unresolved `cd` setting.
+    pub working_directory: Option<PathBuf>,
+    pub checklists: Vec<ChecklistOutcome>,
+    pub defaults: ActionDefaults,
+}
+
+impl<'a, E: CommandExecutor, F: FileAccess, C: Console, K: Clock> ActionContext<'a, E, F, C, K> {
+    pub fn new(
+        system: &'a System,
+        context: Context,
+        executor: &'a E,
+        file_access: &'a F,
+        console: &'a C,
+        clock: &'a K,
+    ) -> Self {
+        Self {
+            system,
+            context,
+            executor,
+            file_access,
+            console,
+            clock,
+            environment: None,
+            exported_variables: HashMap::new(),
+            dotenv_variables: HashMap::new(),
+            working_directory: None,
+            checklists: vec![],
+            defaults: ActionDefaults::default(),
+        }
+    }
+
+    /// Load a dotenv file into the context, and optionally export its values
+    /// to child processes. Returns `false` if the file does not exist.
+    pub fn load_dotenv(&mut self, path: &Path, export: bool) -> color_eyre::Result<bool> {
+        if !self.file_access.file_exists(path) {
+            return Ok(false);
+        }
+
+        info!(
+            message = "Loading dotenv file",
+            path = ?path,
+            export = export
+        );
+
+        let content = self.file_access.read_to_string(path)?;
+        for (key, value) in parse_dotenv(&content) {
+            if export {
+                self.exported_variables.insert(key.clone(), value.clone());
+            }
+
+            self.dotenv_variables.insert(key.clone(), value.clone());
+            self.context.set(key, value);
+        }
+
+        Ok(true)
+    }
+
+    /// Load dotenv files into the context, skipping missing files.
+    pub fn load_dotenv_files(&mut self, paths: &[PathBuf], export: bool) -> color_eyre::Result<()> {
+        for path in paths {
+            let path = path.resolved(&self.context);
+            if !self.load_dotenv(&path, export)? {
+                warn!(
+                    message = "Dotenv file does not exist, skipping",
+                    path = ?path
+                );
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Action {
-    pub fn execute<E: CommandExecutor>(&self, ctx: &ActionContext<E>) -> color_eyre::Result<()> {
+    pub fn execute<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        &self,
+        ctx: &mut ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
         match self {
             Action::Run(action) => {
                 info!(
@@ -70,6 +327,21 @@ impl Action {
                     working_directory = ?action.working_directory
                 );
 
+                if action.single_instance {
+                    let matcher = ProcessMatcher::for_command(
+                        &action.target,
+                        action.args.as_deref().unwrap_or_default(),
+                    )?;
+
+                    if !process::find_processes(ctx.system, ctx.executor, &matcher)?.is_empty() {
+                        info!(
+                            message = "Application is already running, skipping",
+                            target = ?action.target
+                        );
+                        return Ok(());
+                    }
+                }
+
                 let intent = CommandIntent::Custom {
                     target: action.target.clone(),
                     arguments: action
@@ -77,8 +349,17 @@ impl Action {
                         .as_ref()
                         .map(|value| value.iter().map(Into::into).collect())
                         .unwrap_or_default(),
-                    working_directory: action.working_directory.clone(),
-                    creation_type: action.creation_type.clone(),
+                    working_directory: action
+                        .working_directory
+                        .clone()
+                        .or_else(|| ctx.defaults.working_directory.clone()),
+                    input: None,
+                    environment: ctx.exported_variables.clone(),
+                    creation_type: action
+                        .creation_type
+                        .clone()
+                        .or_else(|| ctx.defaults.creation_type.clone())
+                        .unwrap_or_else(CommandCreationType::detach),
                 };
 
                 ctx.executor.execute(intent)?;
@@ -86,43 +367,644 @@ impl Action {
             Action::OpenFile(action) => {
                 info!(
                     message = "Running OpenFile action",
-                    target = ?action.target
+                    target = ?action.target,
+                    with = ?action.with
                 );
 
-                let intent = ctx.system.open_file(&action.target);
-                ctx.executor.execute(intent)?;
+                for target in action.target.as_slice() {
+                    let intent = ctx.system.open_file(target, action.with.as_deref());
+                    ctx.executor.execute(intent)?;
+                }
             }
             Action::ShowMessage(action) => {
                 info!(
                     message = "Running ShowMessage action",
                     data = ?action.message,
+                    level = ?action.level,
+                    pause = action.pause
                 );
 
-                println!("{}", action.message);
+                ctx.console
+                    .message(&action.level, &action.message, action.pause)?;
             }
             Action::OpenUrl(action) => {
                 info!(
                     message = "Running OpenUrl action",
-                    target = %action.target
+                    target = ?action.target,
+                    browser = ?action.browser
                 );
 
-                let intent = ctx.system.open_web_browser(&action.target);
-                ctx.executor.execute(intent)?;
+                // URLs are parsed once resolved, as they can come from variables.
+                let targets = action
+                    .target
+                    .as_slice()
+                    .iter()
+                    .map(|t| Url::parse(t).map_err(|e| eyre!("Invalid URL '{t}': {e}")))
+                    .collect::<color_eyre::Result<Vec<_>>>()?;
+
+                let intents = ctx
+                    .system
+                    .open_web_browser(&targets, action.browser.as_deref())?;
+                for intent in intents {
+                    ctx.executor.execute(intent)?;
+                }
             }
             Action::VSCode(action) => {
                 info!(
                     message = "Running VSCode action",
+                    target = ?action.target,
+                    window = ?action.window,
+                    profile = ?action.profile,
+                    remote = ?action.remote
+                );
+
+                let intent = ctx.system.open_vscode(
+                    ctx.file_access,
+                    action.target.as_slice(),
+                    action.window.as_ref(),
+                    action.profile.as_deref(),
+                    action.remote.as_ref(),
+                )?;
+                ctx.executor.execute(intent)?;
+            }
+            Action::Notify(action) => {
+                info!(
+                    message = "Running Notify action",
+                    title = ?action.title,
+                    body = ?action.body,
+                    urgency = ?action.urgency
+                );
+
+                let intent = ctx.system.send_notification(
+                    &action.title,
+                    &action.body,
+                    action.urgency.as_ref(),
+                    action.icon.as_deref(),
+                );
+                ctx.executor.execute(intent)?;
+            }
+            Action::Clipboard(action) => {
+                info!(
+                    message = "Running Clipboard action",
+                    content = ?action.content
+                );
+
+                let intent = ctx.system.copy_to_clipboard(&action.content);
+                ctx.executor.execute(intent)?;
+            }
+            Action::WriteFile(action) => {
+                info!(
+                    message = "Running WriteFile action",
+                    target = ?action.target,
+                    template = ?action.template,
+                    if_exists = ?action.if_exists
+                );
+
+                Self::write_file(action, ctx)?;
+            }
+            Action::EnsureDir(action) => {
+                info!(
+                    message = "Running EnsureDir action",
                     target = ?action.target
                 );
 
-                let intent = ctx.system.open_vscode(&action.target);
+                filesystem::ensure_dir(ctx.file_access, &action.target)?;
+            }
+            Action::Copy(action) => {
+                info!(
+                    message = "Running Copy action",
+                    source = ?action.source,
+                    target = ?action.target,
+                    overwrite = action.overwrite
+                );
+
+                filesystem::copy(
+                    ctx.file_access,
+                    &action.source,
+                    &action.target,
+                    action.overwrite,
+                )?;
+            }
+            Action::Move(action) => {
+                info!(
+                    message = "Running Move action",
+                    source = ?action.source,
+                    target = ?action.target,
+                    overwrite = action.overwrite
+                );
+
+                filesystem::move_path(
+                    ctx.file_access,
+                    &action.source,
+                    &action.target,
+                    action.overwrite,
+                )?;
+            }
+            Action::Symlink(action) => {
+                info!(
+                    message = "Running Symlink action",
+                    source = ?action.source,
+                    target = ?action.target
+                );
+
+                filesystem::symlink(ctx.file_access, &action.source, &action.target)?;
+            }
+            Action::Remove(action) => {
+                info!(
+                    message = "Running Remove action",
+                    target = ?action.target,
+                    recursive = action.recursive
+                );
+
+                filesystem::remove(
+                    ctx.file_access,
+                    ctx.console,
+                    ctx.system,
+                    &action.target,
+                    action.recursive,
+                )?;
+            }
+            Action::Git(action) => {
+                info!(
+                    message = "Running Git action",
+                    operation = ?action
+                );
+
+                action.execute(ctx.system, ctx.executor, ctx.file_access)?;
+            }
+            Action::Terminal(action) => {
+                info!(
+                    message = "Running Terminal action",
+                    working_directory = ?action.working_directory,
+                    command = ?action.command,
+                    title = ?action.title
+                );
+
+                let intent = ctx.system.open_terminal(
+                    &action.working_directory,
+                    action.title.as_deref(),
+                    action.command.as_deref(),
+                )?;
+                ctx.executor.execute(intent)?;
+            }
+            Action::Tmux(action) => {
+                info!(
+                    message = "Running Tmux action",
+                    session = ?action.session
+                );
+
+                action.execute(ctx.system, ctx.executor)?;
+            }
+            Action::Editor(action) => {
+                info!(
+                    message = "Running Editor action",
+                    editor = ?action.editor,
+                    target = ?action.target,
+                    line = ?action.line
+                );
+
+                let intent = ctx.system.open_in_editor(
+                    &action.editor,
+                    &action.target,
+                    action.line,
+                    ctx.file_access.is_dir(&action.target),
+                )?;
                 ctx.executor.execute(intent)?;
             }
+            Action::KillProcess(action) => {
+                info!(
+                    message = "Running KillProcess action",
+                    name = ?action.name,
+                    command_line = ?action.command_line,
+                    pidfile = ?action.pidfile
+                );
+
+                Self::kill_process(action, ctx)?;
+            }
+            Action::SetVariable(action) => {
+                info!(
+                    message = "Running SetVariable action",
+                    name = ?action.name
+                );
+
+                let value = Self::compute_variable(action, ctx)?;
+                ctx.context.set(action.name.clone(), value);
+            }
+            Action::LoadDotenv(action) => {
+                info!(
+                    message = "Running LoadDotenv action",
+                    path = ?action.path,
+                    export = action.export
+                );
+
+                if !ctx.load_dotenv(&action.path, action.export)? && !action.optional {
+                    bail!("Dotenv file {:?} does not exist", action.path);
+                }
+            }
+            Action::Timer(action) => {
+                info!(
+                    message = "Running Timer action",
+                    work = ?action.work,
+                    break_ = ?action.break_,
+                    cycles = action.cycles
+                );
+
+                // Check the schedule now, to report errors before detaching.
+                action.schedule()?;
+
+                let intent = BackgroundTask::new(self, ctx).spawn_intent()?;
+                ctx.executor.execute(intent)?;
+            }
+            Action::Checklist(action) => {
+                info!(
+                    message = "Running Checklist action",
+                    title = ?action.title,
+                    notes = ?action.notes,
+                    blocking = action.blocking
+                );
+
+                Self::show_checklist(action, ctx)?;
+            }
+            Action::Script(action) => {
+                info!(
+                    message = "Running Script action",
+                    path = ?action.path
+                );
+
+                Self::run_script(action, ctx)?;
+            }
+            Action::Require(action) => {
+                info!(message = "Running Require action");
+
+                action.check(
+                    ctx.executor,
+                    ctx.file_access,
+                    &ctx.context,
+                    &ctx.exported_variables,
+                )?;
+            }
+            Action::SpawnShell(action) => {
+                info!(
+                    message = "Running SpawnShell action",
+                    working_directory = ?action.working_directory
+                );
+
+                // Only exported variables and dotenv values reach the shell, not
+                // every configuration variable.
+                let mut environment = ctx.dotenv_variables.clone();
+                environment.extend(ctx.exported_variables.clone());
+                if let Some(name) = &ctx.environment {
+                    environment.insert("QX_ENVIRONMENT".into(), name.clone());
+                }
+
+                let working_directory = action
+                    .working_directory
+                    .clone()
+                    .or_else(|| ctx.defaults.working_directory.clone())
+                    .or_else(|| ctx.working_directory.resolved(&ctx.context));
+                let intent = ctx
+                    .system
+                    .spawn_shell(working_directory.as_deref(), environment);
+
+                // The shell exits with the status of the last command the user ran,
+                // which is not a boot failure.
+                if let Err(error) = ctx.executor.execute(intent) {
+                    ctx.console.message(
+                        &MessageLevel::Warn,
+                        &format!("The shell exited with an error: {error}"),
+                        false,
+                    )?;
+                }
+            }
+            Action::Watch(action) => {
+                info!(
+                    message = "Running Watch action",
+                    paths = ?action.paths,
+                    include = ?action.include,
+                    ignore = ?action.ignore
+                );
+
+                action.validate()?;
+
+                let intent = BackgroundTask::new(self, ctx).spawn_intent()?;
+                ctx.executor.execute(intent)?;
+            }
+            Action::Backup(action) => {
+                info!(
+                    message = "Running Backup action",
+                    source = ?action.source,
+                    destination = ?action.destination,
+                    format = ?action.format
+                );
+
+                action.run(ctx.file_access, ctx.clock)?;
+            }
+            Action::Foreach(action) => {
+                info!(
+                    message = "Running Foreach action",
+                    foreach = ?action.foreach,
+                    as_ = ?action.as_
+                );
+
+                let previous_value = ctx.context.get(&action.as_).cloned();
+                let previous_list = ctx.context.get_list(&action.as_).cloned();
+
+                let result = Self::run_foreach(action, ctx);
+
+                // The loop variable is restored even when an iteration fails.
+                match (previous_value, previous_list) {
+                    (Some(value), _) => ctx.context.set(action.as_.clone(), value),
+                    (None, Some(values)) => ctx.context.set_list(action.as_.clone(), values),
+                    (None, None) => ctx.context.remove(&action.as_),
+                }
+                result?;
+            }
+            Action::Group(action) => {
+                info!(
+                    message = "Running Group action",
+                    name = ?action.name,
+                    working_directory = ?action.working_directory,
+                    on_failure = ?action.on_failure
+                );
+
+                let previous_defaults = ctx.defaults.clone();
+                let previous_exported_variables = ctx.exported_variables.clone();
+                ctx.defaults = ctx.defaults.merged(action);
+                ctx.exported_variables.extend(action.env.clone());
+
+                let result = Self::run_group(action, ctx);
+
+                ctx.defaults = previous_defaults;
+                ctx.exported_variables = previous_exported_variables;
+                result?;
+            }
         }
 
         Ok(())
     }
 
+    pub fn run_in_background<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        &self,
+        ctx: &mut ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
+        match self {
+            Action::Timer(action) => action.run(ctx.clock, |title, body| {
+                let intent = ctx.system.send_notification(title, body, None, None);
+                ctx.executor.execute(intent)
+            }),
+            Action::Watch(action) => {
+                let (file_access, clock) = (ctx.file_access, ctx.clock);
+                action.run(file_access, clock, |_| {
+                    for nested in &action.actions {
+                        nested.resolved(&ctx.context).execute(ctx)?;
+                    }
+                    Ok(())
+                })
+            }
+            _ => bail!(
+                "Action cannot run in background: {}",
+                self.to_pretty_string()
+            ),
+        }
+    }
+
+    fn run_foreach<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &ActionForeach,
+        ctx: &mut ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
+        for item in action.items(&ctx.context) {
+            ctx.context.set(action.as_.clone(), item);
+            for nested in &action.do_ {
+                nested.resolved(&ctx.context).execute(ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_group<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &ActionGroup,
+        ctx: &mut ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
+        for nested in &action.actions {
+            let Err(error) = nested.resolved(&ctx.context).execute(ctx) else {
+                continue;
+            };
+
+            match action.on_failure {
+                FailurePolicy::Stop => return Err(error),
+                FailurePolicy::Continue => {
+                    warn!(
+                        message = "Action failed, continuing",
+                        group = ?action.name,
+                        error = ?error
+                    );
+
+                    ctx.console.message(
+                        &MessageLevel::Error,
+                        &format!("**{}**: {error}", action.name),
+                        false,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_script<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &ActionScript,
+        ctx: &mut ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
+        let code = match (&action.code, &action.path) {
+            (Some(code), None) => code.clone(),
+            (None, Some(path)) => ctx.file_access.read_to_string(path)?,
+            _ => bail!("Script action needs exactly one of 'code' or 'path'"),
+        };
+
+        let (context, intents) = script::run_script(
+            &code,
+            ctx.system,
+            ctx.file_access,
+            ctx.console,
+            &ctx.context,
+            &ctx.exported_variables,
+        )?;
+
+        ctx.context = context;
+        for intent in intents {
+            ctx.executor.execute(intent)?;
+        }
+
+        Ok(())
+    }
+
+    fn show_checklist<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &ActionChecklist,
+        ctx: &mut ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
+        let mut items = action.items.clone().unwrap_or_default();
+        if let Some(notes) = &action.notes {
+            items.extend(parse_checklist_items(
+                &ctx.file_access.read_to_string(notes)?,
+            ));
+        }
+
+        if items.is_empty() {
+            bail!("Checklist '{}' has no items", action.display_title());
+        }
+
+        let title = action.display_title();
+        let acknowledged = ctx.console.checklist(&title, &items, action.blocking)?;
+        ctx.checklists
+            .push(ChecklistOutcome::new(title, &acknowledged));
+
+        Ok(())
+    }
+
+    fn compute_variable<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &ActionSetVariable,
+        ctx: &ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<String> {
+        let value = match (
+            &action.value,
+            &action.from_file,
+            &action.from_env,
+            &action.first_existing,
+        ) {
+            (Some(value), None, None, None) => Some(value.clone()),
+            (None, Some(path), None, None) => {
+                if ctx.file_access.file_exists(path) {
+                    let content = ctx.file_access.read_to_string(path)?;
+                    Some(content.trim_end_matches(['\r', '\n']).to_string())
+                } else {
+                    None
+                }
+            }
+            (None, None, Some(name), None) => std::env::var(name).ok(),
+            (None, None, None, Some(paths)) => paths
+                .iter()
+                .find(|p| ctx.file_access.file_exists(p))
+                .map(|p| p.to_string_lossy().to_string()),
+            _ => bail!(
+                "set_variable '{}' needs exactly one of 'value', 'from_file', 'from_env' or 'first_existing'",
+                action.name
+            ),
+        };
+
+        match value.or_else(|| action.default.clone()) {
+            Some(value) => Ok(value),
+            None => bail!(
+                "set_variable '{}' could not compute a value and has no default",
+                action.name
+            ),
+        }
+    }
+
+    fn kill_process<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &ActionKillProcess,
+        ctx: &ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
+        if action.name.is_none() && action.command_line.is_none() && action.pidfile.is_none() {
+            bail!("kill_process needs at least one of 'name', 'command_line' or 'pidfile'");
+        }
+
+        let pids = if let Some(pidfile) = &action.pidfile {
+            if !ctx.file_access.file_exists(pidfile) {
+                info!(
+                    message = "Pidfile does not exist, nothing to kill",
+                    pidfile = ?pidfile
+                );
+                return Ok(());
+            }
+
+            match ctx.file_access.read_to_string(pidfile)?.trim().parse() {
+                Ok(pid) => Some(vec![pid]),
+                Err(_) => {
+                    info!(
+                        message = "Pidfile is empty or invalid, nothing to kill",
+                        pidfile = ?pidfile
+                    );
+                    return Ok(());
+                }
+            }
+        } else {
+            None
+        };
+
+        let matcher = ProcessMatcher {
+            name: action.name.clone(),
+            command_line: action
+                .command_line
+                .as_ref()
+                .map(|c| Regex::new(c))
+                .transpose()?,
+            pids,
+        };
+
+        process::kill_processes(
+            ctx.system,
+            ctx.executor,
+            ctx.clock,
+            &matcher,
+            Duration::from_secs(action.grace_period),
+        )
+    }
+
+    fn write_file<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &ActionWriteFile,
+        ctx: &ActionContext<E, F, C, K>,
+    ) -> color_eyre::Result<()> {
+        let content = match (&action.content, &action.template) {
+            (Some(content), None) => content.clone(),
+            (None, Some(template)) => ctx
+                .file_access
+                .read_to_string(template)?
+                .resolved(&ctx.context),
+            _ => bail!(
+                "write_file on {:?} needs exactly one of 'content' or 'template'",
+                action.target
+            ),
+        };
+
+        let exists = ctx.file_access.file_exists(&action.target);
+        if exists {
+            match action.if_exists {
+                FileExistsPolicy::Skip => {
+                    info!(
+                        message = "File already exists, skipping",
+                        target = ?action.target
+                    );
+                    return Ok(());
+                }
+                FileExistsPolicy::Fail => {
+                    bail!("File {:?} already exists", action.target)
+                }
+                FileExistsPolicy::Overwrite | FileExistsPolicy::Append => (),
+            }
+        }
+
+        if let Some(parent_dir) = action.target.parent() {
+            ctx.file_access.create_dir_all(parent_dir)?;
+        }
+
+        if exists && matches!(action.if_exists, FileExistsPolicy::Append) {
+            ctx.file_access.append(&action.target, content)
+        } else {
+            ctx.file_access.write(&action.target, content)
+        }
+    }
+
+    /// Actions nested in this one, for display purposes.
+    pub fn nested_actions(&self) -> &[Action] {
+        match self {
+            Self::Group(action) => &action.actions,
+            Self::Foreach(action) => &action.do_,
+            Self::Watch(action) => &action.actions,
+            _ => &[],
+        }
+    }
+
     pub fn to_pretty_string(&self) -> String {
         match self {
             Self::Run(action) => {
@@ -145,26 +1027,209 @@ impl Action {
                     write!(output, " with working directory {:?}", cwd).unwrap();
                 }
 
-                writeln!(
-                    output,
-                    " using creation type \"{:?}\"",
-                    action.creation_type
-                )
+                if action.single_instance {
+                    write!(output, " as a single instance").unwrap();
+                }
+
+                match &action.creation_type {
+                    Some(creation_type) => {
+                        writeln!(output, " using creation type \"{:?}\"", creation_type)
+                    }
+                    None => writeln!(output),
+                }
                 .unwrap();
 
                 output
             }
             Self::OpenFile(action) => {
-                format!("Open file or folder {:?}", action.target)
+                let mut output = match action.target.as_slice() {
+                    [target] => format!("Open file or folder {:?}", target),
+                    targets => format!(
+                        "Open files or folders [{}]",
+                        targets.iter().map(|t| format!("{t:?}")).join(", ")
+                    ),
+                };
+
+                if let Some(with) = &action.with {
+                    write!(output, " with \"{with}\"").unwrap();
+                }
+
+                output
             }
             Self::OpenUrl(action) => {
-                format!("Open URL \"{}\"", action.target)
-            }
-            Self::ShowMessage(action) => {
-                format!("Show message {:?}", action.message)
+                let mut output = match action.target.as_slice() {
+                    [target] => format!("Open URL \"{}\"", target),
+                    targets => format!(
+                        "Open URLs [{}]",
+                        targets.iter().map(|t| format!("\"{t}\"")).join(", ")
+                    ),
+                };
+
+                if let Some(browser) = &action.browser {
+                    write!(output, " in browser \"{browser}\"").unwrap();
+                }
+
+                output
             }
+            Self::ShowMessage(action) => match action.level {
+                MessageLevel::Info => format!("Show message {:?}", action.message),
+                ref level => format!("Show {} message {:?}", level.label(), action.message),
+            },
             Self::VSCode(action) => {
-                format!("Open VSCode on target {:?}", action.target)
+                let mut output = match action.target.as_slice() {
+                    [target] => format!("Open VSCode on target {:?}", target),
+                    targets => format!(
+                        "Open VSCode on targets [{}]",
+                        targets.iter().map(|t| format!("{t:?}")).join(", ")
+                    ),
+                };
+
+                if let Some(remote) = &action.remote {
+                    write!(output, " on {}", remote.to_pretty_string()).unwrap();
+                }
+
+                if let Some(profile) = &action.profile {
+                    write!(output, " with profile \"{profile}\"").unwrap();
+                }
+
+                output
+            }
+            Self::Notify(action) => {
+                format!("Send notification {:?}", action.title)
+            }
+            Self::Clipboard(action) => {
+                format!("Copy {:?} to clipboard", action.content)
+            }
+            Self::WriteFile(action) => {
+                if let Some(template) = &action.template {
+                    format!(
+                        "Write file {:?} from template {:?} (if exists: {:?})",
+                        action.target, template, action.if_exists
+                    )
+                } else {
+                    format!(
+                        "Write file {:?} (if exists: {:?})",
+                        action.target, action.if_exists
+                    )
+                }
+            }
+            Self::EnsureDir(action) => {
+                format!("Ensure directory {:?} exists", action.target)
+            }
+            Self::Copy(action) => {
+                format!("Copy {:?} to {:?}", action.source, action.target)
+            }
+            Self::Move(action) => {
+                format!("Move {:?} to {:?}", action.source, action.target)
+            }
+            Self::Symlink(action) => {
+                format!("Link {:?} to {:?}", action.target, action.source)
+            }
+            Self::Remove(action) => {
+                if action.recursive {
+                    format!("Remove {:?} recursively", action.target)
+                } else {
+                    format!("Remove {:?}", action.target)
+                }
+            }
+            Self::Git(action) => action.to_pretty_string(),
+            Self::Terminal(action) => {
+                let mut output = format!("Open terminal in {:?}", action.working_directory);
+                if let Some(command) = &action.command {
+                    write!(output, " running \"{command}\"").unwrap();
+                }
+
+                output
+            }
+            Self::Tmux(action) => action.to_pretty_string(),
+            Self::Editor(action) => {
+                let mut output = format!("Open {} on target {:?}", action.editor, action.target);
+                if let Some(line) = action.line {
+                    write!(output, " at line {line}").unwrap();
+                }
+
+                output
+            }
+            Self::SetVariable(action) => {
+                let source = if let Some(value) = &action.value {
+                    format!("\"{value}\"")
+                } else if let Some(path) = &action.from_file {
+                    format!("content of file {path:?}")
+                } else if let Some(name) = &action.from_env {
+                    format!("environment variable \"{name}\"")
+                } else if let Some(paths) = &action.first_existing {
+                    format!(
+                        "first existing path of [{}]",
+                        paths.iter().map(|p| format!("{p:?}")).join(", ")
+                    )
+                } else {
+                    "nothing".into()
+                };
+
+                format!("Set variable \"{}\" to {}", action.name, source)
+            }
+            Self::LoadDotenv(action) => {
+                if action.export {
+                    format!("Load dotenv file {:?} and export its values", action.path)
+                } else {
+                    format!("Load dotenv file {:?}", action.path)
+                }
+            }
+            Self::Timer(action) => {
+                format!(
+                    "Start a focus timer of {} cycles ({} work, {} break)",
+                    action.cycles, action.work, action.break_
+                )
+            }
+            Self::Checklist(action) => {
+                let source = match (&action.items, &action.notes) {
+                    (Some(items), None) => format!("{} items", items.len()),
+                    (None, Some(notes)) => format!("items from {notes:?}"),
+                    (Some(items), Some(notes)) => {
+                        format!("{} items and items from {notes:?}", items.len())
+                    }
+                    (None, None) => "no items".into(),
+                };
+
+                if action.blocking {
+                    format!(
+                        "Show checklist \"{}\" ({source}) until every item is acknowledged",
+                        action.display_title()
+                    )
+                } else {
+                    format!("Show checklist \"{}\" ({source})", action.display_title())
+                }
+            }
+            Self::Require(action) => action.to_pretty_string(),
+            Self::Watch(action) => action.to_pretty_string(),
+            Self::Foreach(action) => action.to_pretty_string(),
+            Self::Group(action) => action.to_pretty_string(),
+            Self::Backup(action) => action.to_pretty_string(),
+            Self::SpawnShell(action) => match &action.working_directory {
+                Some(working_directory) => format!("Spawn a shell in {working_directory:?}"),
+                None => "Spawn a shell".into(),
+            },
+            Self::Script(action) => match &action.path {
+                Some(path) => format!("Run script {path:?}"),
+                None => "Run inline script".into(),
+            },
+            Self::KillProcess(action) => {
+                let mut criteria = vec![];
+                if let Some(name) = &action.name {
+                    criteria.push(format!("named \"{name}\""));
+                }
+                if let Some(command_line) = &action.command_line {
+                    criteria.push(format!("with command line matching \"{command_line}\""));
+                }
+                if let Some(pidfile) = &action.pidfile {
+                    criteria.push(format!("from pidfile {pidfile:?}"));
+                }
+
+                format!(
+                    "Kill processes {} (grace period: {}s)",
+                    criteria.join(" and "),
+                    action.grace_period
+                )
             }
         }
     }
@@ -183,13 +1248,441 @@ impl Resolvable for Action {
             }
             Self::OpenUrl(cmd) => {
                 cmd.target.resolve(ctx);
+                cmd.browser.resolve(ctx);
             }
             Self::OpenFile(cmd) => {
                 cmd.target.resolve(ctx);
+                cmd.with.resolve(ctx);
             }
             Self::VSCode(cmd) => {
                 cmd.target.resolve(ctx);
+                cmd.profile.resolve(ctx);
+                cmd.remote.resolve(ctx);
+            }
+            Self::Notify(cmd) => {
+                cmd.title.resolve(ctx);
+                cmd.body.resolve(ctx);
+                cmd.icon.resolve(ctx);
+            }
+            Self::Clipboard(cmd) => {
+                cmd.content.resolve(ctx);
+            }
+            Self::WriteFile(cmd) => {
+                cmd.target.resolve(ctx);
+                cmd.content.resolve(ctx);
+                cmd.template.resolve(ctx);
+            }
+            Self::EnsureDir(cmd) => {
+                cmd.target.resolve(ctx);
+            }
+            Self::Copy(cmd) => {
+                cmd.source.resolve(ctx);
+                cmd.target.resolve(ctx);
+            }
+            Self::Move(cmd) => {
+                cmd.source.resolve(ctx);
+                cmd.target.resolve(ctx);
+            }
+            Self::Symlink(cmd) => {
+                cmd.source.resolve(ctx);
+                cmd.target.resolve(ctx);
+            }
+            Self::Remove(cmd) => {
+                cmd.target.resolve(ctx);
+            }
+            Self::Git(cmd) => {
+                cmd.resolve(ctx);
+            }
+            Self::Terminal(cmd) => {
+                cmd.working_directory.resolve(ctx);
+                cmd.command.resolve(ctx);
+                cmd.title.resolve(ctx);
+            }
+            Self::Tmux(cmd) => {
+                cmd.resolve(ctx);
+            }
+            Self::Editor(cmd) => {
+                cmd.editor.resolve(ctx);
+                cmd.target.resolve(ctx);
+            }
+            Self::KillProcess(cmd) => {
+                cmd.name.resolve(ctx);
+                cmd.command_line.resolve(ctx);
+                cmd.pidfile.resolve(ctx);
+            }
+            Self::SetVariable(cmd) => {
+                cmd.value.resolve(ctx);
+                cmd.from_file.resolve(ctx);
+                cmd.from_env.resolve(ctx);
+                cmd.first_existing.resolve(ctx);
+                cmd.default.resolve(ctx);
+            }
+            Self::LoadDotenv(cmd) => {
+                cmd.path.resolve(ctx);
+            }
+            Self::Timer(cmd) => {
+                cmd.work.resolve(ctx);
+                cmd.break_.resolve(ctx);
+            }
+            Self::Checklist(cmd) => {
+                cmd.title.resolve(ctx);
+                cmd.items.resolve(ctx);
+                cmd.notes.resolve(ctx);
+            }
+            Self::Require(cmd) => {
+                cmd.resolve(ctx);
+            }
+            Self::SpawnShell(cmd) => {
+                cmd.working_directory.resolve(ctx);
+            }
+            Self::Watch(cmd) => {
+                cmd.resolve(ctx);
+            }
+            Self::Foreach(_) => {
+                // Items and nested actions are resolved while iterating.
+            }
+            Self::Group(cmd) => {
+                cmd.resolve(ctx);
+            }
+            Self::Backup(cmd) => {
+                cmd.resolve(ctx);
             }
+            Self::Script(cmd) => {
+                // The code is not resolved, scripts read variables with `get_var`.
+                cmd.path.resolve(ctx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::Platform;
+    use crate::testing::{FakeClock, FakeConsole, MemoryFileAccess, RecordingExecutor};
+    use crate::Environment;
+
+    fn run(
+        action: &str,
+        file_access: &MemoryFileAccess,
+        executor: &RecordingExecutor,
+    ) -> color_eyre::Result<()> {
+        run_on(Platform::default(), action, file_access, executor)
+    }
+
+    fn run_on(
+        platform: Platform,
+        action: &str,
+        file_access: &MemoryFileAccess,
+        executor: &RecordingExecutor,
+    ) -> color_eyre::Result<()> {
+        let action: Action = serde_yaml::from_str(action).unwrap();
+        let mut system = System::default();
+        system.platform = platform;
+        let console = FakeConsole::default();
+        let clock = FakeClock::default();
+        let mut ctx = ActionContext::new(
+            &system,
+            Context::empty(),
+            executor,
+            file_access,
+            &console,
+            &clock,
+        );
+
+        action.execute(&mut ctx)
+    }
+
+    /// Run an action on a platform, returning the single intent it emitted.
+    fn single_intent(platform: Platform, action: &str) -> CommandIntent {
+        let executor = RecordingExecutor::default();
+        run_on(platform, action, &MemoryFileAccess::default(), &executor).unwrap();
+
+        let mut intents = executor.intents.take();
+        assert_eq!(intents.len(), 1, "Unexpected intents {intents:?}");
+        intents.remove(0)
+    }
+
+    #[test]
+    fn notify_sends_the_platform_notification_command() {
+        let action = r#"{type: notify, title: Build, body: It's "green", urgency: critical, icon: /icons/qx.png}"#;
+
+        let CommandIntent::Custom {
+            target, arguments, ..
+        } = single_intent(Platform::Linux { wayland: false }, action)
+        else {
+            panic!("Expected a custom command");
+        };
+        assert_eq!(target, Path::new("notify-send"));
+        assert_eq!(
+            arguments,
+            [
+                "--urgency=critical",
+                "--icon=/icons/qx.png",
+                "--app-name=qx",
+                "Build",
+                "It's \"green\"",
+            ]
+        );
+
+        let CommandIntent::Custom {
+            target, arguments, ..
+        } = single_intent(Platform::MacOs, action)
+        else {
+            panic!("Expected a custom command");
+        };
+        assert_eq!(target, Path::new("osascript"));
+        assert_eq!(
+            arguments,
+            [
+                "-e",
+                r#"display notification "It's \"green\"" with title "Build""#,
+            ]
+        );
+
+        let CommandIntent::Custom {
+            target, arguments, ..
+        } = single_intent(Platform::Windows, action)
+        else {
+            panic!("Expected a custom command");
+        };
+        assert_eq!(target, Path::new("powershell"));
+        assert_eq!(
+            arguments[..4],
+            ["-NoProfile", "-WindowStyle", "Hidden", "-Command"]
+        );
+        assert!(arguments[4].contains("New-Object System.Drawing.Icon('/icons/qx.png')"));
+        assert!(arguments[4].contains("$n.BalloonTipIcon = 'Error'"));
+        assert!(arguments[4].contains(r#"ShowBalloonTip(10000, 'Build', 'It''s "green"'"#));
+    }
+
+    #[test]
+    fn clipboard_pipes_the_content_to_the_platform_command() {
+        for (platform, expected) in [
+            (Platform::Windows, "clip.exe"),
+            (Platform::MacOs, "pbcopy"),
+            (Platform::Linux { wayland: true }, "wl-copy"),
+            (
+                Platform::Linux { wayland: false },
+                "xclip -selection clipboard",
+            ),
+        ] {
+            let intent = single_intent(platform, "{type: clipboard, content: token}");
+
+            let CommandIntent::Custom {
+                target,
+                arguments,
+                input,
+                creation_type,
+                ..
+            } = intent
+            else {
+                panic!("Expected a custom command");
+            };
+            let command = std::iter::once(target.to_string_lossy().to_string())
+                .chain(arguments)
+                .join(" ");
+            assert_eq!(command, expected);
+            assert_eq!(input.as_deref(), Some("token"));
+            assert!(matches!(creation_type, CommandCreationType::Wait));
+        }
+    }
+
+    #[test]
+    fn open_file_opens_every_target_with_the_application() {
+        let executor = RecordingExecutor::default();
+
+        run(
+            "{type: open_file, target: [/notes/a.md, /notes/b.png], with: gimp}",
+            &MemoryFileAccess::default(),
+            &executor,
+        )
+        .unwrap();
+
+        let intents = executor.intents.borrow();
+        assert_eq!(intents.len(), 2);
+        for (intent, expected) in intents.iter().zip(["/notes/a.md", "/notes/b.png"]) {
+            let CommandIntent::System {
+                target,
+                application,
+                creation_type,
+            } = intent
+            else {
+                panic!("Expected a system command");
+            };
+            assert_eq!(target, Path::new(expected));
+            assert_eq!(application.as_deref(), Some("gimp"));
+            assert!(matches!(creation_type, CommandCreationType::Detach));
+        }
+    }
+
+    #[test]
+    fn open_file_uses_the_default_application_without_with() {
+        let CommandIntent::System { application, .. } = single_intent(
+            Platform::default(),
+            "{type: open_file, target: /notes/a.md}",
+        ) else {
+            panic!("Expected a system command");
+        };
+        assert_eq!(application, None);
+    }
+
+    #[test]
+    fn kill_process_treats_invalid_pidfiles_as_stale() {
+        for content in ["", "not a pid\n"] {
+            let file_access = MemoryFileAccess::default().with_file("/run/app.pid", content);
+            let executor = RecordingExecutor::default();
+
+            run(
+                "{type: kill_process, pidfile: /run/app.pid}",
+                &file_access,
+                &executor,
+            )
+            .unwrap();
+            assert!(executor.commands().is_empty());
         }
     }
+
+    #[test]
+    fn run_skips_single_instances_already_running() {
+        let executor = RecordingExecutor::default()
+            .with_output(true, "7 python3 /srv/my app/serve.py --port 80\n");
+
+        run(
+            r#"{type: run, target: "/srv/my app/serve.py", args: ["--port", "80"], single_instance: true}"#,
+            &MemoryFileAccess::default(),
+            &executor,
+        )
+        .unwrap();
+        assert_eq!(executor.commands().len(), 1);
+    }
+
+    #[test]
+    fn spawn_shell_exports_dotenv_and_exported_values_in_the_environment_directory() {
+        let file_access = MemoryFileAccess::default().with_file("/code/.env", "TOKEN=abc\n");
+        let executor = RecordingExecutor::default();
+        let system = System::default();
+        let console = FakeConsole::default();
+        let clock = FakeClock::default();
+        let mut ctx = ActionContext::new(
+            &system,
+            Context::new([("project".to_string(), "qx".to_string())].into()),
+            &executor,
+            &file_access,
+            &console,
+            &clock,
+        );
+        ctx.exported_variables
+            .insert("PORT".to_string(), "8080".to_string());
+
+        let environment = Environment {
+            name: "dev".into(),
+            description: String::new(),
+            actions: vec![serde_yaml::from_str("type: spawn_shell").unwrap()],
+            dotenv: vec!["/code/.env".into()],
+            export_dotenv: false,
+            cd: Some("/code/${project}".into()),
+            export: HashMap::new(),
+        };
+        environment.boot(&mut ctx).unwrap();
+
+        let intents = executor.intents.borrow();
+        let CommandIntent::Custom {
+            working_directory,
+            environment,
+            ..
+        } = &intents[0]
+        else {
+            panic!("Unexpected intent {:?}", intents[0]);
+        };
+        assert_eq!(working_directory.as_deref(), Some(Path::new("/code/qx")));
+        assert_eq!(
+            environment
+                .iter()
+                .collect::<std::collections::BTreeMap<_, _>>(),
+            [
+                (&"PORT".to_string(), &"8080".to_string()),
+                (&"QX_ENVIRONMENT".to_string(), &"dev".to_string()),
+                (&"TOKEN".to_string(), &"abc".to_string()),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn spawn_shell_tolerates_the_shell_exit_status() {
+        let executor = RecordingExecutor::default().failing_on("sh");
+        let system: System = serde_yaml::from_str("shell_path: sh").unwrap();
+        let console = FakeConsole::default();
+        let clock = FakeClock::default();
+        let file_access = MemoryFileAccess::default();
+        let mut ctx = ActionContext::new(
+            &system,
+            Context::empty(),
+            &executor,
+            &file_access,
+            &console,
+            &clock,
+        );
+
+        let action: Action = serde_yaml::from_str("type: spawn_shell").unwrap();
+        action.execute(&mut ctx).unwrap();
+        assert_eq!(console.messages.borrow()[0].0, MessageLevel::Warn);
+    }
+
+    #[test]
+    fn foreach_restores_the_loop_variable_when_an_iteration_fails() {
+        let executor = RecordingExecutor::default().failing_on("deploy b");
+        let system = System::default();
+        let console = FakeConsole::default();
+        let clock = FakeClock::default();
+        let file_access = MemoryFileAccess::default();
+        let mut context = Context::empty();
+        context.set_list("item".into(), vec!["x".into(), "y".into()]);
+        let mut ctx =
+            ActionContext::new(&system, context, &executor, &file_access, &console, &clock);
+
+        let action: Action = serde_yaml::from_str(
+            r#"
+            type: foreach
+            foreach: [a, b, c]
+            as: item
+            do:
+              - type: run
+                target: deploy
+                args: ["${item}"]
+                creation_type: wait
+            "#,
+        )
+        .unwrap();
+
+        assert!(action.execute(&mut ctx).is_err());
+        assert_eq!(executor.commands(), ["deploy a", "deploy b"]);
+        assert_eq!(ctx.context.get("item"), None);
+        assert_eq!(
+            ctx.context.get_list("item"),
+            Some(&vec!["x".to_string(), "y".to_string()])
+        );
+    }
+
+    #[test]
+    fn run_pretty_string_ends_with_a_new_line() {
+        let action: Action =
+            serde_yaml::from_str("{type: run, target: code, args: [.], creation_type: wait}")
+                .unwrap();
+
+        assert_eq!(
+            action.to_pretty_string(),
+            "Run application \"code\" with args [\".\"] using creation type \"Wait\"\n"
+        );
+    }
+
+    #[test]
+    fn run_does_not_write_missing_creation_types() {
+        let action: Action = serde_yaml::from_str("{type: run, target: code}").unwrap();
+
+        assert!(!serde_yaml::to_string(&action)
+            .unwrap()
+            .contains("creation_type"));
+    }
 }
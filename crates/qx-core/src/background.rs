@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::Action, context::Context, group::ActionDefaults, intent::CommandIntent, ActionContext,
+    Clock, CommandExecutor, Console, FileAccess, System,
+};
+
+/// An action running in a detached qx process, like a timer.
+///
+/// The task is sent to the `qx --background` process on its standard input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundTask {
+    pub environment: Option<String>,
+    pub system: System,
+    pub variables: HashMap<String, String>,
+    #[serde(default)]
+    pub lists: HashMap<String, Vec<String>>,
+    pub exported_variables: HashMap<String, String>,
+    #[serde(default)]
+    pub dotenv_variables: HashMap<String, String>,
+    /// Defaults of the groups the action is in.
+    #[serde(default)]
+    pub defaults: ActionDefaults,
+    pub action: Action,
+}
+
+impl BackgroundTask {
+    pub fn new<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        action: &Action,
+        ctx: &ActionContext<E, F, C, K>,
+    ) -> Self {
+        Self {
+            environment: ctx.environment.clone(),
+            system: ctx.system.clone(),
+            variables: ctx.context.values.clone(),
+            lists: ctx.context.lists.clone(),
+            exported_variables: ctx.exported_variables.clone(),
+            dotenv_variables: ctx.dotenv_variables.clone(),
+            defaults: ctx.defaults.clone(),
+            action: action.clone(),
+        }
+    }
+
+    pub fn from_yaml(data: &str) -> Result<Self> {
+        serde_yaml::from_str(data).map_err(Into::into)
+    }
+
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self).map_err(Into::into)
+    }
+
+    pub fn spawn_intent(&self) -> Result<CommandIntent> {
+        self.system.spawn_background(self.to_yaml()?)
+    }
+
+    pub fn description(&self) -> String {
+        self.action.to_pretty_string()
+    }
+
+    pub fn run<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        &self,
+        executor: &E,
+        file_access: &F,
+        console: &C,
+        clock: &K,
+    ) -> Result<()> {
+        let mut ctx = ActionContext::new(
+            &self.system,
+            Context {
+                values: self.variables.clone(),
+                lists: self.lists.clone(),
+                ..Default::default()
+            },
+            executor,
+            file_access,
+            console,
+            clock,
+        );
+        ctx.environment.clone_from(&self.environment);
+        ctx.exported_variables.clone_from(&self.exported_variables);
+        ctx.dotenv_variables.clone_from(&self.dotenv_variables);
+        ctx.defaults = self.defaults.clone();
+
+        self.action.run_in_background(&mut ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+    use crate::testing::{FakeClock, FakeConsole, MemoryFileAccess, RecordingExecutor};
+
+    /// A clock changing a watched file on its first sleep, and stopping the
+    /// endless watch loop with a panic after a few sleeps.
+    struct TouchingClock {
+        clock: FakeClock,
+        file_access: MemoryFileAccess,
+    }
+
+    impl Clock for TouchingClock {
+        fn now(&self) -> SystemTime {
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.clock.sleep(duration);
+            match self.clock.elapsed().as_secs() {
+                1 => self
+                    .file_access
+                    .write(Path::new("/code/src/main.rs"), "changed")
+                    .unwrap(),
+                s if s > 10 => panic!("Stop watching"),
+                _ => (),
+            }
+        }
+    }
+
+    #[test]
+    fn watch_keeps_the_group_defaults_and_variables() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/code/src/main.rs", "")
+            .with_file("/code/.env", "TOKEN=abc\n");
+        let executor = RecordingExecutor::default();
+        let system = System::default();
+        let console = FakeConsole::default();
+        let clock = FakeClock::default();
+        let mut ctx = ActionContext::new(
+            &system,
+            Context::empty(),
+            &executor,
+            &file_access,
+            &console,
+            &clock,
+        );
+        ctx.load_dotenv(Path::new("/code/.env"), false).unwrap();
+
+        let action: Action = serde_yaml::from_str(
+            r#"
+            type: group
+            name: dev
+            working_directory: /code
+            env: {MODE: dev}
+            actions:
+              - type: watch
+                paths: /code/src
+                actions:
+                  - type: run
+                    target: make
+            "#,
+        )
+        .unwrap();
+        action.execute(&mut ctx).unwrap();
+
+        // The task is sent to the background process on its standard input.
+        let CommandIntent::Custom {
+            input: Some(data), ..
+        } = executor.intents.take().remove(0)
+        else {
+            panic!("Expected the background process to be started");
+        };
+        let task = BackgroundTask::from_yaml(&data).unwrap();
+        assert_eq!(task.dotenv_variables.get("TOKEN").unwrap(), "abc");
+
+        let task = BackgroundTask::from_yaml(&task.to_yaml().unwrap()).unwrap();
+        let clock = TouchingClock {
+            clock: FakeClock::default(),
+            file_access: file_access.clone(),
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            task.run(&executor, &file_access, &console, &clock)
+        }));
+        assert!(result.is_err());
+
+        let intents = executor.intents.borrow();
+        assert_eq!(intents.len(), 1);
+        let CommandIntent::Custom {
+            target,
+            working_directory,
+            environment,
+            ..
+        } = &intents[0]
+        else {
+            panic!("Unexpected intent {:?}", intents[0]);
+        };
+        assert_eq!(target, Path::new("make"));
+        assert_eq!(working_directory.as_deref(), Some(Path::new("/code")));
+        assert_eq!(environment.get("MODE").unwrap(), "dev");
+    }
+}
@@ -0,0 +1,492 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Local};
+use color_eyre::{eyre::bail, Result};
+use flate2::{write::GzEncoder, Compression};
+use globset::GlobSet;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    context::Context, filesystem::resolve_path, resolvable::Resolvable, watch::build_glob_set,
+    Clock, FileAccess, WriteSeek,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupFormat {
+    #[default]
+    TarGz,
+    Zip,
+}
+
+impl BackupFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionBackup {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub format: BackupFormat,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    pub keep: Option<usize>,
+}
+
+/// A file to put in the archive, with its path relative to the backup source.
+struct BackupFile {
+    path: PathBuf,
+    relative: String,
+    modified: SystemTime,
+}
+
+/// Files and folders to archive.
+struct Selection {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Resolved source and destination, to skip snapshots stored in the source.
+    source: PathBuf,
+    destination: PathBuf,
+}
+
+impl ActionBackup {
+    pub fn to_pretty_string(&self) -> String {
+        let mut output = format!(
+            "Backup {:?} to {:?} as {}",
+            self.source,
+            self.destination,
+            self.format.extension()
+        );
+
+        if let Some(keep) = self.keep {
+            output.push_str(&format!(", keeping the last {keep} snapshots"));
+        }
+
+        output
+    }
+
+    /// Archive the source folder, then remove the oldest snapshots.
+    ///
+    /// Returns the path of the created archive.
+    pub fn run<F: FileAccess, K: Clock>(&self, file_access: &F, clock: &K) -> Result<PathBuf> {
+        if !file_access.is_dir(&self.source) {
+            bail!("Backup source {:?} is not a directory", self.source);
+        }
+
+        if self.keep == Some(0) {
+            bail!("Backup 'keep' must be at least 1, or it would remove the new snapshot");
+        }
+
+        let selection = Selection {
+            include: if self.include.is_empty() {
+                None
+            } else {
+                Some(build_glob_set(&self.include)?)
+            },
+            exclude: build_glob_set(&self.exclude)?,
+            source: resolve_path(file_access, &self.source),
+            destination: resolve_path(file_access, &self.destination),
+        };
+
+        let mut files = vec![];
+        self.collect_files(file_access, &selection, &self.source, &mut files)?;
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+        file_access.create_dir_all(&self.destination)?;
+        let archive = self.archive_path(file_access, clock);
+
+        // Write under a temporary name, so a failed backup never leaves a
+        // truncated snapshot behind.
+        let mut partial = archive.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let result = file_access
+            .create(&partial)
+            .and_then(|output| match self.format {
+                BackupFormat::TarGz => Self::write_tar_gz(file_access, &files, output),
+                BackupFormat::Zip => Self::write_zip(file_access, &files, output),
+            });
+        if let Err(error) = result.and_then(|_| file_access.rename(&partial, &archive)) {
+            let _ = file_access.remove_file(&partial);
+            return Err(error);
+        }
+
+        info!(
+            message = "Backup created",
+            archive = ?archive,
+            files = files.len()
+        );
+
+        if let Some(keep) = self.keep {
+            self.remove_old_snapshots(file_access, &archive, keep)?;
+        }
+
+        Ok(archive)
+    }
+
+    /// Path of a new snapshot, with a counter when one was already created in the same second.
+    fn archive_path<F: FileAccess, K: Clock>(&self, file_access: &F, clock: &K) -> PathBuf {
+        let timestamp = DateTime::<Local>::from(clock.now()).format("%Y%m%d-%H%M%S");
+        let extension = self.format.extension();
+
+        let mut archive = self
+            .destination
+            .join(format!("{}-{timestamp}.{extension}", self.prefix()));
+        let mut counter = 1;
+        while file_access.file_exists(&archive) {
+            counter += 1;
+            archive = self.destination.join(format!(
+                "{}-{timestamp}-{counter}.{extension}",
+                self.prefix()
+            ));
+        }
+
+        archive
+    }
+
+    /// Archive name prefix, defaulting to the source folder name.
+    fn prefix(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.source
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "backup".into())
+        })
+    }
+
+    fn collect_files<F: FileAccess>(
+        &self,
+        file_access: &F,
+        selection: &Selection,
+        path: &Path,
+        files: &mut Vec<BackupFile>,
+    ) -> Result<()> {
+        let relative = path.strip_prefix(&self.source).unwrap_or(path);
+        if path != self.source && selection.exclude.is_match(relative) {
+            return Ok(());
+        }
+
+        if file_access.is_dir(path) {
+            // Linked folders are not followed, as they can point to one of their parents.
+            if path != self.source && file_access.is_symlink(path) {
+                warn!(
+                    message = "Skipping linked folder",
+                    path = ?path
+                );
+                return Ok(());
+            }
+
+            // Skip previous snapshots when they are stored in the source folder.
+            if selection.source.join(relative) == selection.destination {
+                return Ok(());
+            }
+
+            for child in file_access.read_dir(path)? {
+                self.collect_files(file_access, selection, &child, files)?;
+            }
+            return Ok(());
+        }
+
+        let included = selection
+            .include
+            .as_ref()
+            .map_or(true, |i| i.is_match(relative));
+        if included {
+            files.push(BackupFile {
+                path: path.to_path_buf(),
+                // Archives always use forward slashes.
+                relative: relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                modified: file_access.modified(path)?,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn write_tar_gz<F: FileAccess>(
+        file_access: &F,
+        files: &[BackupFile],
+        output: Box<dyn WriteSeek>,
+    ) -> Result<()> {
+        let mut builder = tar::Builder::new(GzEncoder::new(output, Compression::default()));
+        for file in files {
+            let size = file_access.file_size(&file.path)?;
+
+            let mut header = tar::Header::new_gnu();
+            header.set_size(size);
+            header.set_mode(file_access.permissions(&file.path)?);
+            header.set_mtime(
+                file.modified
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+            );
+            header.set_cksum();
+
+            // Files changing while archived are cut or padded to the size in their header.
+            let data = file_access
+                .open(&file.path)?
+                .take(size)
+                .chain(std::io::repeat(0))
+                .take(size);
+            builder.append_data(&mut header, &file.relative, data)?;
+        }
+
+        builder.into_inner()?.finish()?.flush()?;
+        Ok(())
+    }
+
+    fn write_zip<F: FileAccess>(
+        file_access: &F,
+        files: &[BackupFile],
+        output: Box<dyn WriteSeek>,
+    ) -> Result<()> {
+        let mut writer = ZipWriter::new(output);
+        for file in files {
+            let options = FileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .unix_permissions(file_access.permissions(&file.path)?)
+                .large_file(file_access.file_size(&file.path)? >= u32::MAX.into());
+
+            writer.start_file(file.relative.as_str(), options)?;
+            std::io::copy(&mut file_access.open(&file.path)?, &mut writer)?;
+        }
+
+        writer.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Remove the oldest snapshots, never the one just created.
+    fn remove_old_snapshots<F: FileAccess>(
+        &self,
+        file_access: &F,
+        archive: &Path,
+        keep: usize,
+    ) -> Result<()> {
+        let pattern = Regex::new(&format!(
+            r"^{}-(\d{{8}}-\d{{6}})(?:-(\d+))?\.{}$",
+            regex::escape(&self.prefix()),
+            regex::escape(self.format.extension())
+        ))?;
+
+        let mut snapshots: Vec<_> = file_access
+            .read_dir(&self.destination)?
+            .into_iter()
+            .filter(|path| path != archive)
+            .filter_map(|path| {
+                let name = path.file_name()?.to_string_lossy().to_string();
+                let captures = pattern.captures(&name)?;
+                let timestamp = captures[1].to_string();
+                let counter: u64 = captures
+                    .get(2)
+                    .map_or(Some(1), |c| c.as_str().parse().ok())?;
+
+                Some(((timestamp, counter), path))
+            })
+            .collect();
+        snapshots.sort();
+
+        let count = snapshots.len().saturating_sub(keep - 1);
+        for (_, snapshot) in &snapshots[..count] {
+            info!(
+                message = "Removing old backup",
+                archive = ?snapshot
+            );
+            file_access.remove_file(snapshot)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Resolvable for ActionBackup {
+    fn resolve(&mut self, ctx: &Context) {
+        self.source.resolve(ctx);
+        self.destination.resolve(ctx);
+        self.name.resolve(ctx);
+        self.include.resolve(ctx);
+        self.exclude.resolve(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, time::Duration};
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+    use crate::testing::{FakeClock, MemoryFileAccess};
+
+    fn backup(yaml: &str) -> ActionBackup {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn entries(file_access: &MemoryFileAccess, archive: &Path) -> Vec<String> {
+        let data = file_access.read(archive).unwrap();
+        let mut archive = tar::Archive::new(GzDecoder::new(data.as_slice()));
+
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                format!("{}={content}", entry.path().unwrap().display())
+            })
+            .collect()
+    }
+
+    fn names(file_access: &MemoryFileAccess, folder: &str) -> Vec<String> {
+        file_access
+            .read_dir(Path::new(folder))
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn run_archives_files_without_following_linked_folders() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/code/src/main.rs", "fn main() {}")
+            .with_file("/code/notes.tmp", "")
+            .with_file("/elsewhere/secret", "")
+            .with_symlink("/code", "/code/src/loop")
+            .with_symlink("/elsewhere", "/code/linked");
+        let action = backup("{source: /code, destination: /backups, exclude: ['*.tmp']}");
+
+        let archive = action.run(&file_access, &FakeClock::default()).unwrap();
+
+        assert_eq!(
+            entries(&file_access, &archive),
+            ["src/main.rs=fn main() {}"]
+        );
+    }
+
+    #[test]
+    fn run_skips_the_destination_reached_through_a_link() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/code/main.rs", "")
+            .with_dir("/code/.snapshots")
+            .with_symlink("/code/.snapshots", "/backups");
+        let action = backup("{source: /code, destination: /backups}");
+
+        let clock = FakeClock::default();
+        action.run(&file_access, &clock).unwrap();
+        let archive = action.run(&file_access, &clock).unwrap();
+
+        assert_eq!(entries(&file_access, &archive), ["main.rs="]);
+    }
+
+    #[test]
+    fn run_does_not_overwrite_snapshots_of_the_same_second() {
+        let file_access = MemoryFileAccess::default().with_file("/code/main.rs", "");
+        let action = backup("{source: /code, destination: /backups, name: app}");
+        let clock = FakeClock::default();
+
+        let first = action.run(&file_access, &clock).unwrap();
+        let second = action.run(&file_access, &clock).unwrap();
+
+        assert_ne!(first, second);
+        assert!(second.to_string_lossy().ends_with("-2.tar.gz"));
+    }
+
+    #[test]
+    fn run_keeps_the_newest_snapshots_only() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/code/main.rs", "")
+            .with_file("/backups/app-20000101-000000.tar.gz", "")
+            .with_file("/backups/app-20000101-000001.tar.gz", "")
+            .with_file("/backups/app-extra-20000101-000000.tar.gz", "")
+            .with_file("/backups/app-notes.tar.gz", "");
+        let action = backup("{source: /code, destination: /backups, name: app, keep: 2}");
+        let clock = FakeClock::default();
+        clock.sleep(Duration::from_secs(1_000_000_000));
+
+        let archive = action.run(&file_access, &clock).unwrap();
+
+        let mut expected = vec![
+            "app-20000101-000001.tar.gz".to_string(),
+            "app-extra-20000101-000000.tar.gz".into(),
+            "app-notes.tar.gz".into(),
+            archive.file_name().unwrap().to_string_lossy().to_string(),
+        ];
+        expected.sort();
+        assert_eq!(names(&file_access, "/backups"), expected);
+    }
+
+    #[test]
+    fn run_rejects_keeping_no_snapshots() {
+        let file_access = MemoryFileAccess::default().with_file("/code/main.rs", "");
+        let action = backup("{source: /code, destination: /backups, keep: 0}");
+
+        assert!(action.run(&file_access, &FakeClock::default()).is_err());
+        assert!(!file_access.file_exists(Path::new("/backups")));
+    }
+
+    #[test]
+    fn run_keeps_file_permissions() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/code/run.sh", "#!/bin/sh")
+            .with_permissions("/code/run.sh", 0o755)
+            .with_file("/code/notes.md", "");
+
+        let action = backup("{source: /code, destination: /backups}");
+        let archive = action.run(&file_access, &FakeClock::default()).unwrap();
+        let data = file_access.read(&archive).unwrap();
+        let modes: Vec<_> = tar::Archive::new(GzDecoder::new(data.as_slice()))
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().header().mode().unwrap())
+            .collect();
+        assert_eq!(modes, [0o644, 0o755]);
+
+        let action = backup("{source: /code, destination: /backups, format: zip}");
+        let archive = action.run(&file_access, &FakeClock::default()).unwrap();
+        let data = file_access.read(&archive).unwrap();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        let mut file = zip.by_name("run.sh").unwrap();
+        assert_eq!(file.unix_mode().unwrap() & 0o7777, 0o755);
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "#!/bin/sh");
+    }
+
+    #[test]
+    fn run_does_not_leave_failed_snapshots() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/code/a.txt", "a")
+            .with_file("/code/b.txt", "b")
+            .with_file("/backups/code-20000101-000000.tar.gz", "")
+            .failing_on("/code/b.txt");
+        let action = backup("{source: /code, destination: /backups, keep: 1}");
+
+        assert!(action.run(&file_access, &FakeClock::default()).is_err());
+        assert_eq!(
+            names(&file_access, "/backups"),
+            ["code-20000101-000000.tar.gz"]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::resolvable::fill_placeholders;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrowserKind {
+    #[default]
+    Chromium,
+    Firefox,
+    Custom,
+}
+
+/// A named web browser configuration.
+///
+/// Argument templates can use the `${profile_directory}` variable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserProfile {
+    #[serde(default)]
+    pub kind: BrowserKind,
+    pub path: PathBuf,
+    pub arguments: Option<Vec<String>>,
+    #[serde(default)]
+    pub new_window: bool,
+    #[serde(default)]
+    pub incognito: bool,
+    pub profile_directory: Option<String>,
+    pub new_window_arguments: Option<Vec<String>>,
+    pub incognito_arguments: Option<Vec<String>>,
+    pub profile_arguments: Option<Vec<String>>,
+}
+
+impl BrowserProfile {
+    fn preset_new_window_arguments(&self) -> Vec<&'static str> {
+        match self.kind {
+            BrowserKind::Chromium | BrowserKind::Firefox => vec!["--new-window"],
+            BrowserKind::Custom => vec![],
+        }
+    }
+
+    fn preset_incognito_arguments(&self) -> Vec<&'static str> {
+        match self.kind {
+            BrowserKind::Chromium => vec!["--incognito"],
+            BrowserKind::Firefox => vec!["--private-window"],
+            BrowserKind::Custom => vec![],
+        }
+    }
+
+    fn preset_profile_arguments(&self) -> Vec<&'static str> {
+        match self.kind {
+            BrowserKind::Chromium => vec!["--profile-directory=${profile_directory}"],
+            BrowserKind::Firefox => vec!["-P", "${profile_directory}"],
+            BrowserKind::Custom => vec![],
+        }
+    }
+
+    pub(crate) fn build_arguments(&self, targets: &[String]) -> Vec<String> {
+        let profile_directory = self.profile_directory.clone().unwrap_or_default();
+        let values = [("profile_directory", profile_directory.as_str())];
+
+        let templates = |custom: &Option<Vec<String>>, preset: Vec<&'static str>| {
+            custom
+                .clone()
+                .unwrap_or_else(|| preset.into_iter().map(Into::into).collect())
+                .into_iter()
+                .map(|a| fill_placeholders(&a, &values))
+                .collect::<Vec<_>>()
+        };
+
+        let mut arguments = self.arguments.clone().unwrap_or_default();
+        if self.profile_directory.is_some() {
+            arguments.extend(templates(
+                &self.profile_arguments,
+                self.preset_profile_arguments(),
+            ));
+        }
+        if self.incognito {
+            arguments.extend(templates(
+                &self.incognito_arguments,
+                self.preset_incognito_arguments(),
+            ));
+        }
+        if self.new_window {
+            arguments.extend(templates(
+                &self.new_window_arguments,
+                self.preset_new_window_arguments(),
+            ));
+        }
+
+        arguments.extend(targets.iter().cloned());
+        arguments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_arguments_uses_preset_arguments() {
+        let profile: BrowserProfile = serde_yaml::from_str(
+            "{kind: firefox, path: firefox, incognito: true, profile_directory: work}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            profile.build_arguments(&["https://example.com".into()]),
+            ["-P", "work", "--private-window", "https://example.com"]
+        );
+    }
+
+    #[test]
+    fn build_arguments_inserts_values_literally() {
+        let profile: BrowserProfile =
+            serde_yaml::from_str("{path: chrome, profile_directory: '%TEMP%/${x}'}").unwrap();
+
+        assert_eq!(
+            profile.build_arguments(&[]),
+            ["--profile-directory=%TEMP%/${x}"]
+        );
+    }
+}
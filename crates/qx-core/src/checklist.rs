@@ -0,0 +1,146 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionChecklist {
+    pub title: Option<String>,
+    pub items: Option<Vec<String>>,
+    pub notes: Option<PathBuf>,
+    #[serde(default)]
+    pub blocking: bool,
+}
+
+impl ActionChecklist {
+    pub fn display_title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| "Checklist".into())
+    }
+}
+
+/// How a checklist shown during a boot ended.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistOutcome {
+    pub title: String,
+    pub items: usize,
+    pub acknowledged: usize,
+    pub completed: bool,
+}
+
+impl ChecklistOutcome {
+    pub fn new(title: String, acknowledged: &[bool]) -> Self {
+        let count = acknowledged.iter().filter(|a| **a).count();
+
+        Self {
+            title,
+            items: acknowledged.len(),
+            acknowledged: count,
+            completed: count == acknowledged.len(),
+        }
+    }
+}
+
+/// Extract checklist items from a markdown notes file.
+///
+/// Bullet (`-`, `*`, `+`) and numbered list items are kept, with their
+/// optional `[ ]` / `[x]` task marker removed.
+pub fn parse_checklist_items(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let item = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .or_else(|| line.strip_prefix("+ "))
+                .or_else(|| {
+                    let (number, rest) = line.split_once(". ")?;
+                    let is_number =
+                        !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+                    is_number.then_some(rest)
+                })?
+                .trim();
+
+            let item = ["[ ]", "[x]", "[X]"]
+                .iter()
+                .find_map(|marker| item.strip_prefix(marker))
+                .unwrap_or(item)
+                .trim();
+
+            (!item.is_empty()).then(|| item.to_string())
+        })
+        .collect()
+}
+
+/// Parse the items acknowledged in a numbered checklist, like `1 3-5, 7`.
+///
+/// Returns the sorted indexes of the selected items, without duplicates,
+/// and the values which are not item numbers or ranges.
+pub(crate) fn parse_checklist_selection(answer: &str, count: usize) -> (Vec<usize>, Vec<String>) {
+    let mut selected = BTreeSet::new();
+    let mut unknown = vec![];
+
+    for value in answer
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|v| !v.is_empty())
+    {
+        let (start, end) = value.split_once('-').unwrap_or((value, value));
+        match (start.parse::<usize>(), end.parse::<usize>()) {
+            (Ok(start), Ok(end)) if 1 <= start && start <= end && end <= count => {
+                selected.extend(start - 1..end);
+            }
+            _ => unknown.push(value.to_string()),
+        }
+    }
+
+    (selected.into_iter().collect(), unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_checklist_items_keeps_list_items() {
+        let notes =
+            "# Notes\n- [ ] backup\n* [x] deploy\n+ review\n12. tag\nplain text\n-\n1.5 no\n";
+
+        assert_eq!(
+            parse_checklist_items(notes),
+            ["backup", "deploy", "review", "tag"]
+        );
+    }
+
+    #[test]
+    fn selection_accepts_numbers_and_ranges() {
+        assert_eq!(
+            parse_checklist_selection("1 3-4,6", 6),
+            (vec![0, 2, 3, 5], vec![])
+        );
+    }
+
+    #[test]
+    fn selection_ignores_duplicates() {
+        assert_eq!(
+            parse_checklist_selection("2 2 1-3", 3),
+            (vec![0, 1, 2], vec![])
+        );
+    }
+
+    #[test]
+    fn selection_reports_invalid_values() {
+        assert_eq!(
+            parse_checklist_selection("0 2 4 3-1 2-9 x -", 3),
+            (
+                vec![1],
+                ["0", "4", "3-1", "2-9", "x", "-"]
+                    .map(String::from)
+                    .to_vec()
+            )
+        );
+    }
+
+    #[test]
+    fn selection_of_empty_input() {
+        assert_eq!(parse_checklist_selection(" , ", 3), (vec![], vec![]));
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use itertools::Itertools;
 
@@ -9,6 +9,8 @@ pub struct Configuration {
     pub system: System,
     pub variables: Context,
     pub environments: HashMap<String, Environment>,
+    pub dotenv: Vec<PathBuf>,
+    pub export_dotenv: bool,
 }
 
 impl Configuration {
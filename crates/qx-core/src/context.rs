@@ -1,18 +1,207 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+/// A variable value, as written in the configuration file.
+///
+/// Numbers and booleans are read as their text, like `port: 8080`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Variable {
+    Value(String),
+    List(Vec<String>),
+}
+
+/// A string, number or boolean, as text.
+struct Scalar(String);
+
+struct ScalarVisitor;
+
+impl<'de> Visitor<'de> for ScalarVisitor {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string, a number or a boolean")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ScalarVisitor).map(Scalar)
+    }
+}
+
+struct VariableVisitor;
+
+impl<'de> Visitor<'de> for VariableVisitor {
+    type Value = Variable;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string, a number, a boolean or a list of them")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_bool(v).map(Variable::Value)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_i64(v).map(Variable::Value)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_u64(v).map(Variable::Value)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_f64(v).map(Variable::Value)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_str(v).map(Variable::Value)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = vec![];
+        while let Some(Scalar(value)) = seq.next_element()? {
+            values.push(value);
+        }
+
+        Ok(Variable::List(values))
+    }
+}
+
+impl<'de> Deserialize<'de> for Variable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(VariableVisitor)
+    }
+}
 
 #[derive(Debug, Clone, Default)]
-pub struct Context(pub HashMap<String, String>);
+pub struct Context {
+    pub values: HashMap<String, String>,
+    pub lists: HashMap<String, Vec<String>>,
+    /// Keep unknown variables as is instead of replacing them with nothing.
+    pub(crate) keep_missing: bool,
+}
 
 impl Context {
     pub fn new(values: HashMap<String, String>) -> Self {
-        Self(values)
+        Self {
+            values,
+            ..Default::default()
+        }
     }
 
     pub fn empty() -> Self {
-        Self(HashMap::default())
+        Self::default()
+    }
+
+    /// A copy of the context for display, keeping variables which are only
+    /// known during the boot as `${name}`.
+    pub fn for_display(&self) -> Self {
+        Self {
+            keep_missing: true,
+            ..self.clone()
+        }
+    }
+
+    pub fn from_variables(variables: HashMap<String, Variable>) -> Self {
+        let mut context = Self::empty();
+        for (key, variable) in variables {
+            match variable {
+                Variable::Value(value) => context.set(key, value),
+                Variable::List(values) => context.set_list(key, values),
+            }
+        }
+
+        context
+    }
+
+    pub fn to_variables(&self) -> HashMap<String, Variable> {
+        self.values
+            .iter()
+            .map(|(k, v)| (k.clone(), Variable::Value(v.clone())))
+            .chain(
+                self.lists
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Variable::List(v.clone()))),
+            )
+            .collect()
     }
 
     pub fn get(&self, key: &str) -> Option<&String> {
-        self.0.get(key)
+        self.values.get(key)
+    }
+
+    pub fn get_list(&self, key: &str) -> Option<&Vec<String>> {
+        self.lists.get(key)
+    }
+
+    pub fn set(&mut self, key: String, value: String) {
+        self.lists.remove(&key);
+        self.values.insert(key, value);
+    }
+
+    pub fn set_list(&mut self, key: String, values: Vec<String>) {
+        self.values.remove(&key);
+        self.lists.insert(key, values);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+        self.lists.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_accept_scalars_and_lists() {
+        let variables: HashMap<String, Variable> = serde_yaml::from_str(
+            "{port: 8080, ratio: 1.5, offset: -2, flag: true, name: qx, hosts: [a, 1, false]}",
+        )
+        .unwrap();
+        let value = |name: &str| variables[name].clone();
+
+        assert_eq!(value("port"), Variable::Value("8080".into()));
+        assert_eq!(value("ratio"), Variable::Value("1.5".into()));
+        assert_eq!(value("offset"), Variable::Value("-2".into()));
+        assert_eq!(value("flag"), Variable::Value("true".into()));
+        assert_eq!(value("name"), Variable::Value("qx".into()));
+        assert_eq!(
+            value("hosts"),
+            Variable::List(vec!["a".into(), "1".into(), "false".into()])
+        );
+    }
+
+    #[test]
+    fn variables_reject_maps() {
+        assert!(serde_yaml::from_str::<Variable>("{a: 1}").is_err());
+        assert!(serde_yaml::from_str::<Variable>("[[a]]").is_err());
     }
 }
@@ -0,0 +1,116 @@
+/// Parse the content of a dotenv file.
+///
+/// Supports comments, `export` prefixes, single-quoted (raw) values and
+/// double-quoted values with `\n`, `\t`, `\"` and `\\` escapes.
+pub fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+
+            Some((key.to_string(), parse_value(value.trim())))
+        })
+        .collect()
+}
+
+fn parse_value(value: &str) -> String {
+    if let Some(inner) = value
+        .strip_prefix('\'')
+        .and_then(|v| v.split_once('\''))
+        .map(|(inner, _)| inner)
+    {
+        return inner.to_string();
+    }
+
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut output = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => output.push('\n'),
+                    Some('t') => output.push('\t'),
+                    Some(other) => output.push(other),
+                    None => output.push('\\'),
+                },
+                other => output.push(other),
+            }
+        }
+
+        return output;
+    }
+
+    // Unquoted values can end with an inline comment.
+    match value.split_once(" #") {
+        Some((value, _)) => value.trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn quoted_values() {
+        assert_eq!(
+            parse_dotenv(
+                r#"
+                RAW='C:\new #1' # single quotes are raw
+                ESCAPED="line\n\ttab \"quoted\" \\ #2" # comment
+                UNCLOSED="abc
+                "#
+            ),
+            [
+                pair("RAW", r"C:\new #1"),
+                pair("ESCAPED", "line\n\ttab \"quoted\" \\ #2"),
+                pair("UNCLOSED", "abc"),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_and_export_prefixes() {
+        assert_eq!(
+            parse_dotenv(
+                "# database\nexport DB_HOST=localhost # local only\n  PORT = 5432\nURL=http://host/#anchor\n"
+            ),
+            [
+                pair("DB_HOST", "localhost"),
+                pair("PORT", "5432"),
+                pair("URL", "http://host/#anchor"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_values() {
+        assert_eq!(
+            parse_dotenv("EMPTY=\nQUOTED=''\nDOUBLE=\"\"\n"),
+            [pair("EMPTY", ""), pair("QUOTED", ""), pair("DOUBLE", "")]
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        assert_eq!(
+            parse_dotenv("NO_VALUE\n=orphan\n   \nexport\nKEY=value\n"),
+            [pair("KEY", "value")]
+        );
+    }
+}
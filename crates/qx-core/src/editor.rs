@@ -0,0 +1,165 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::resolvable::fill_placeholders;
+
+/// How to open files and folders in an editor.
+///
+/// Argument templates can use the `${path}`, `${line}` and `${uri}` variables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorProfile {
+    pub path: PathBuf,
+    #[serde(default = "EditorProfile::default_arguments")]
+    pub folder_arguments: Vec<String>,
+    #[serde(default = "EditorProfile::default_arguments")]
+    pub file_arguments: Vec<String>,
+    pub line_arguments: Option<Vec<String>>,
+    pub remote_arguments: Option<Vec<String>>,
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+pub(crate) enum EditorTarget<'a> {
+    Folder(&'a str),
+    File(&'a str),
+    FileAtLine(&'a str, u32),
+    Remote(&'a str),
+}
+
+impl EditorProfile {
+    fn default_arguments() -> Vec<String> {
+        vec!["${path}".into()]
+    }
+
+    fn preset(
+        path: PathBuf,
+        line_arguments: &[&str],
+        remote_arguments: Option<&[&str]>,
+        terminal: bool,
+    ) -> Self {
+        let to_vec = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        Self {
+            path,
+            folder_arguments: Self::default_arguments(),
+            file_arguments: Self::default_arguments(),
+            line_arguments: Some(to_vec(line_arguments)),
+            remote_arguments: remote_arguments.map(to_vec),
+            terminal,
+        }
+    }
+
+    pub(crate) fn presets(vscode_path: PathBuf) -> HashMap<String, Self> {
+        let vscode_line: &[&str] = &["--goto", "${path}:${line}"];
+        let vscode_remote: &[&str] = &["--folder-uri", "${uri}"];
+        let jetbrains_line: &[&str] = &["--line", "${line}", "${path}"];
+
+        let mut presets = HashMap::from([
+            (
+                "vscode".into(),
+                Self::preset(vscode_path, vscode_line, Some(vscode_remote), false),
+            ),
+            (
+                "vscodium".into(),
+                Self::preset("codium".into(), vscode_line, Some(vscode_remote), false),
+            ),
+            (
+                "zed".into(),
+                Self::preset("zed".into(), &["${path}:${line}"], Some(&["${uri}"]), false),
+            ),
+            (
+                "sublime".into(),
+                Self::preset("subl".into(), &["${path}:${line}"], None, false),
+            ),
+            (
+                "neovim".into(),
+                Self::preset("nvim".into(), &["+${line}", "${path}"], None, true),
+            ),
+        ]);
+
+        for ide in [
+            "idea",
+            "pycharm",
+            "webstorm",
+            "phpstorm",
+            "clion",
+            "goland",
+            "rider",
+            "rustrover",
+        ] {
+            presets.insert(
+                ide.into(),
+                Self::preset(ide.into(), jetbrains_line, None, false),
+            );
+        }
+
+        presets
+    }
+
+    /// Build the arguments for a target, or `None` if the editor does not support remote URIs.
+    pub(crate) fn build_arguments(&self, target: &EditorTarget) -> Option<Vec<String>> {
+        let line = match target {
+            EditorTarget::FileAtLine(_, line) => line.to_string(),
+            _ => String::new(),
+        };
+
+        let (templates, values) = match target {
+            EditorTarget::Folder(path) => (&self.folder_arguments, vec![("path", *path)]),
+            EditorTarget::File(path) => (&self.file_arguments, vec![("path", *path)]),
+            EditorTarget::FileAtLine(path, _) => (
+                self.line_arguments.as_ref().unwrap_or(&self.file_arguments),
+                vec![("path", *path), ("line", line.as_str())],
+            ),
+            EditorTarget::Remote(uri) => (self.remote_arguments.as_ref()?, vec![("uri", *uri)]),
+        };
+
+        Some(
+            templates
+                .iter()
+                .map(|a| fill_placeholders(a, &values))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_arguments_fills_line_templates() {
+        let profile = EditorProfile::presets("code".into())
+            .remove("vscode")
+            .unwrap();
+
+        assert_eq!(
+            profile.build_arguments(&EditorTarget::FileAtLine("src/main.rs", 12)),
+            Some(vec!["--goto".into(), "src/main.rs:12".into()])
+        );
+    }
+
+    #[test]
+    fn build_arguments_inserts_paths_literally() {
+        let profile = EditorProfile::presets("code".into())
+            .remove("vscode")
+            .unwrap();
+
+        assert_eq!(
+            profile.build_arguments(&EditorTarget::File("/tmp/100%/%HOME%/${x}.txt")),
+            Some(vec!["/tmp/100%/%HOME%/${x}.txt".into()])
+        );
+    }
+
+    #[test]
+    fn build_arguments_rejects_remote_targets_without_templates() {
+        let profile = EditorProfile::presets("code".into())
+            .remove("sublime")
+            .unwrap();
+
+        assert_eq!(
+            profile.build_arguments(&EditorTarget::Remote("ssh://host/path")),
+            None
+        );
+    }
+}
@@ -1,23 +1,62 @@
 use crate::{
-    actions::Action, context::Context, resolvable::Resolvable, ActionContext, CommandExecutor,
+    actions::Action,
+    context::Context,
+    resolvable::{Resolvable, ResolvableClone},
+    shell::{check_variable_name, ShellChanges},
+    ActionContext, Clock, CommandExecutor, Console, FileAccess,
 };
 use color_eyre::Result;
+use std::{collections::HashMap, path::PathBuf};
 
 #[derive(Debug)]
 pub struct Environment {
     pub name: String,
     pub description: String,
     pub actions: Vec<Action>,
+    pub dotenv: Vec<PathBuf>,
+    pub export_dotenv: bool,
+    pub cd: Option<PathBuf>,
+    pub export: HashMap<String, String>,
 }
 
 impl Environment {
-    pub fn boot<E: CommandExecutor>(&self, context: &ActionContext<E>) -> Result<()> {
+    pub fn boot<E: CommandExecutor, F: FileAccess, C: Console, K: Clock>(
+        &self,
+        context: &mut ActionContext<E, F, C, K>,
+    ) -> Result<()> {
+        self.validate_exports()?;
+
+        context.environment = Some(self.name.clone());
+        context.working_directory.clone_from(&self.cd);
+        context.load_dotenv_files(&self.dotenv, self.export_dotenv)?;
+
+        // Actions are resolved just before their execution, so they can use
+        // variables set by previous actions.
         for action in &self.actions {
-            action.execute(context)?;
+            action.resolved(&context.context).execute(context)?;
         }
 
         Ok(())
     }
+
+    /// Check the names of the variables to export to the calling shell.
+    pub fn validate_exports(&self) -> Result<()> {
+        self.export
+            .keys()
+            .try_for_each(|name| check_variable_name(name))
+    }
+
+    /// Changes to apply to the calling shell, resolved with the context after the boot.
+    pub fn shell_changes(&self, ctx: &Context) -> ShellChanges {
+        ShellChanges {
+            working_directory: self.cd.resolved(ctx),
+            environment: self
+                .export
+                .iter()
+                .map(|(k, v)| (k.clone(), v.resolved(ctx)))
+                .collect(),
+        }
+    }
 }
 
 impl std::fmt::Display for Environment {
@@ -29,8 +68,5 @@ impl std::fmt::Display for Environment {
 impl Resolvable for Environment {
     fn resolve(&mut self, ctx: &Context) {
         self.description.resolve(ctx);
-        for action in &mut self.actions {
-            action.resolve(ctx);
-        }
     }
 }
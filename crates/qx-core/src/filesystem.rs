@@ -0,0 +1,338 @@
+use std::path::{Component, Path, PathBuf};
+
+use color_eyre::{eyre::bail, Result};
+use tracing::{info, warn};
+
+use crate::{Console, FileAccess, System};
+
+/// Lexically normalize a path, removing `.` and resolving `..` components
+/// without touching the filesystem.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut output = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !output.pop() {
+                    output.push(component);
+                }
+            }
+            other => output.push(other),
+        }
+    }
+
+    output
+}
+
+/// Resolve symlinks in the longest existing part of a path, the rest of the
+/// path being normalized lexically.
+pub(crate) fn resolve_path<F: FileAccess>(file_access: &F, path: &Path) -> PathBuf {
+    let path = normalize_path(path);
+
+    let mut existing = path.as_path();
+    loop {
+        if let Ok(resolved) = file_access.canonicalize(existing) {
+            return resolved.join(path.strip_prefix(existing).unwrap());
+        }
+
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return path,
+        }
+    }
+}
+
+pub(crate) fn ensure_dir<F: FileAccess>(file_access: &F, target: &Path) -> Result<()> {
+    if file_access.file_exists(target) && !file_access.is_dir(target) {
+        bail!("Path {:?} already exists and is not a directory", target);
+    }
+
+    file_access.create_dir_all(target)
+}
+
+pub(crate) fn copy<F: FileAccess>(
+    file_access: &F,
+    source: &Path,
+    target: &Path,
+    overwrite: bool,
+) -> Result<()> {
+    if !file_access.file_exists(source) {
+        bail!("Source path {:?} does not exist", source);
+    }
+
+    if file_access.is_dir(source)
+        && resolve_path(file_access, target).starts_with(resolve_path(file_access, source))
+    {
+        bail!("Cannot copy {:?} into itself at {:?}", source, target);
+    }
+
+    copy_path(file_access, source, target, overwrite, &mut vec![])
+}
+
+/// Copy a path, `ancestors` holding the canonical folders being copied to
+/// skip symlink loops.
+fn copy_path<F: FileAccess>(
+    file_access: &F,
+    source: &Path,
+    target: &Path,
+    overwrite: bool,
+    ancestors: &mut Vec<PathBuf>,
+) -> Result<()> {
+    if file_access.is_dir(source) {
+        let canonical = file_access.canonicalize(source)?;
+        if ancestors.contains(&canonical) {
+            warn!(
+                message = "Symlink loop, skipping",
+                source = ?source
+            );
+            return Ok(());
+        }
+
+        ancestors.push(canonical);
+        file_access.create_dir_all(target)?;
+        for entry in file_access.read_dir(source)? {
+            if let Some(name) = entry.file_name() {
+                copy_path(
+                    file_access,
+                    &entry,
+                    &target.join(name),
+                    overwrite,
+                    ancestors,
+                )?;
+            }
+        }
+        ancestors.pop();
+
+        return Ok(());
+    }
+
+    if file_access.file_exists(target) && !overwrite {
+        bail!("Target path {:?} already exists", target);
+    }
+
+    if let Some(parent_dir) = target.parent() {
+        file_access.create_dir_all(parent_dir)?;
+    }
+
+    file_access.copy_file(source, target)
+}
+
+pub(crate) fn move_path<F: FileAccess>(
+    file_access: &F,
+    source: &Path,
+    target: &Path,
+    overwrite: bool,
+) -> Result<()> {
+    if !file_access.file_exists(source) {
+        bail!("Source path {:?} does not exist", source);
+    }
+
+    if file_access.file_exists(target) && !overwrite {
+        bail!("Target path {:?} already exists", target);
+    }
+
+    if let Some(parent_dir) = target.parent() {
+        file_access.create_dir_all(parent_dir)?;
+    }
+
+    file_access.rename(source, target)
+}
+
+pub(crate) fn symlink<F: FileAccess>(file_access: &F, source: &Path, target: &Path) -> Result<()> {
+    if file_access.file_exists(target) {
+        bail!("Target path {:?} already exists", target);
+    }
+
+    if let Some(parent_dir) = target.parent() {
+        file_access.create_dir_all(parent_dir)?;
+    }
+
+    file_access.symlink(source, target)
+}
+
+pub(crate) fn remove<F: FileAccess, C: Console>(
+    file_access: &F,
+    console: &C,
+    system: &System,
+    target: &Path,
+    recursive: bool,
+) -> Result<()> {
+    if !system.is_removable_path(file_access, target) {
+        bail!(
+            "Refusing to remove {:?}: path is not in the system 'removable_paths' allowlist",
+            target
+        );
+    }
+
+    let is_symlink = file_access.is_symlink(target);
+    if !file_access.file_exists(target) && !is_symlink {
+        info!(
+            message = "Path does not exist, nothing to remove",
+            target = ?target
+        );
+        return Ok(());
+    }
+
+    if is_symlink || !file_access.is_dir(target) {
+        return file_access.remove_file(target);
+    }
+
+    if !recursive {
+        return file_access.remove_dir(target);
+    }
+
+    if !console.confirm(&format!("Remove {:?} and all its content?", target))? {
+        bail!("Recursive removal of {:?} was not confirmed", target);
+    }
+
+    file_access.remove_dir_all(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeConsole, MemoryFileAccess};
+
+    fn system() -> System {
+        serde_yaml::from_str("removable_paths: [/home/me/tmp]").unwrap()
+    }
+
+    #[test]
+    fn remove_deletes_files_in_allowlist() {
+        let fs = MemoryFileAccess::default().with_file("/home/me/tmp/a.txt", "a");
+
+        remove(
+            &fs,
+            &FakeConsole::default(),
+            &system(),
+            Path::new("/home/me/tmp/a.txt"),
+            false,
+        )
+        .unwrap();
+
+        assert!(!fs.file_exists(Path::new("/home/me/tmp/a.txt")));
+    }
+
+    #[test]
+    fn remove_refuses_paths_outside_allowlist() {
+        let fs = MemoryFileAccess::default().with_file("/home/me/a.txt", "a");
+
+        let result = remove(
+            &fs,
+            &FakeConsole::default(),
+            &system(),
+            Path::new("/home/me/tmp/../a.txt"),
+            false,
+        );
+
+        assert!(result.is_err());
+        assert!(fs.file_exists(Path::new("/home/me/a.txt")));
+    }
+
+    #[test]
+    fn remove_refuses_paths_escaping_through_symlinks() {
+        let fs = MemoryFileAccess::default()
+            .with_file("/etc/passwd", "root")
+            .with_dir("/home/me/tmp")
+            .with_symlink("/etc", "/home/me/tmp/escape");
+
+        let result = remove(
+            &fs,
+            &FakeConsole::default(),
+            &system(),
+            Path::new("/home/me/tmp/escape/passwd"),
+            false,
+        );
+
+        assert!(result.is_err());
+        assert!(fs.file_exists(Path::new("/etc/passwd")));
+    }
+
+    #[test]
+    fn remove_deletes_symlinks_but_not_their_target() {
+        let fs = MemoryFileAccess::default()
+            .with_file("/etc/passwd", "root")
+            .with_dir("/home/me/tmp")
+            .with_symlink("/etc", "/home/me/tmp/escape");
+
+        remove(
+            &fs,
+            &FakeConsole::default(),
+            &system(),
+            Path::new("/home/me/tmp/escape"),
+            true,
+        )
+        .unwrap();
+
+        assert!(!fs.is_symlink(Path::new("/home/me/tmp/escape")));
+        assert!(fs.file_exists(Path::new("/etc/passwd")));
+    }
+
+    #[test]
+    fn remove_asks_before_recursive_removal() {
+        let fs = MemoryFileAccess::default().with_file("/home/me/tmp/dir/a.txt", "a");
+        let path = Path::new("/home/me/tmp/dir");
+
+        assert!(remove(&fs, &FakeConsole::default(), &system(), path, true).is_err());
+        assert!(fs.file_exists(path));
+
+        remove(&fs, &FakeConsole::confirming(), &system(), path, true).unwrap();
+        assert!(!fs.file_exists(path));
+    }
+
+    #[test]
+    fn copy_copies_folders() {
+        let fs = MemoryFileAccess::default()
+            .with_file("/src/a.txt", "a")
+            .with_file("/src/sub/b.txt", "b");
+
+        copy(&fs, Path::new("/src"), Path::new("/dst"), false).unwrap();
+
+        assert_eq!(fs.read_to_string(Path::new("/dst/a.txt")).unwrap(), "a");
+        assert_eq!(fs.read_to_string(Path::new("/dst/sub/b.txt")).unwrap(), "b");
+    }
+
+    #[test]
+    fn copy_refuses_to_overwrite() {
+        let fs = MemoryFileAccess::default()
+            .with_file("/src/a.txt", "a")
+            .with_file("/dst/a.txt", "old");
+
+        assert!(copy(&fs, Path::new("/src"), Path::new("/dst"), false).is_err());
+        assert_eq!(fs.read_to_string(Path::new("/dst/a.txt")).unwrap(), "old");
+
+        copy(&fs, Path::new("/src"), Path::new("/dst"), true).unwrap();
+        assert_eq!(fs.read_to_string(Path::new("/dst/a.txt")).unwrap(), "a");
+    }
+
+    #[test]
+    fn copy_refuses_to_copy_a_folder_into_itself() {
+        let fs = MemoryFileAccess::default()
+            .with_file("/src/a.txt", "a")
+            .with_symlink("/src", "/link");
+
+        assert!(copy(&fs, Path::new("/src"), Path::new("/src/backup"), false).is_err());
+        assert!(copy(&fs, Path::new("/src"), Path::new("/link/backup"), false).is_err());
+        assert!(!fs.file_exists(Path::new("/src/backup")));
+    }
+
+    #[test]
+    fn copy_skips_symlink_loops() {
+        let fs = MemoryFileAccess::default()
+            .with_file("/src/a.txt", "a")
+            .with_symlink("/src", "/src/loop");
+
+        copy(&fs, Path::new("/src"), Path::new("/dst"), false).unwrap();
+
+        assert!(fs.file_exists(Path::new("/dst/a.txt")));
+        assert!(!fs.file_exists(Path::new("/dst/loop")));
+    }
+
+    #[test]
+    fn normalize_path_resolves_parent_components() {
+        assert_eq!(
+            normalize_path(Path::new("/home/me/./tmp/../a.txt")),
+            PathBuf::from("/home/me/a.txt")
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::Action, context::Context, one_or_many::OneOrMany, resolvable::ResolvableClone,
+};
+
+static LIST_VARIABLE_RGX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\$\{(.*?)\}$").unwrap());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionForeach {
+    pub foreach: OneOrMany<String>,
+    #[serde(rename = "as")]
+    pub as_: String,
+    #[serde(rename = "do")]
+    pub do_: Vec<Action>,
+}
+
+impl ActionForeach {
+    /// Expand the items to iterate on, a `${name}` item referencing a list
+    /// variable being replaced by the list values.
+    pub fn items(&self, ctx: &Context) -> Vec<String> {
+        self.foreach
+            .as_slice()
+            .iter()
+            .flat_map(|item| {
+                let list = LIST_VARIABLE_RGX
+                    .captures(item)
+                    .and_then(|caps| ctx.get_list(caps.get(1).unwrap().as_str()));
+
+                match list {
+                    Some(values) => values.iter().map(|v| v.resolved(ctx)).collect(),
+                    None => vec![item.resolved(ctx)],
+                }
+            })
+            .collect()
+    }
+
+    pub fn to_pretty_string(&self) -> String {
+        format!(
+            "For each \"{}\" in {}",
+            self.as_,
+            match self.foreach.as_slice() {
+                [item] => item.clone(),
+                items => format!("[{}]", items.join(", ")),
+            }
+        )
+    }
+}
@@ -0,0 +1,342 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::{eyre::bail, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{context::Context, resolvable::Resolvable, CommandExecutor, FileAccess, System};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum ActionGit {
+    EnsureClone {
+        url: String,
+        path: PathBuf,
+        branch: Option<String>,
+    },
+    Pull {
+        path: PathBuf,
+    },
+    Checkout {
+        path: PathBuf,
+        branch: String,
+    },
+    WorktreeAdd {
+        path: PathBuf,
+        worktree_path: PathBuf,
+        branch: Option<String>,
+    },
+}
+
+impl ActionGit {
+    pub(crate) fn execute<E: CommandExecutor, F: FileAccess>(
+        &self,
+        system: &System,
+        executor: &E,
+        file_access: &F,
+    ) -> Result<()> {
+        match self {
+            Self::EnsureClone { url, path, branch } => {
+                if file_access.file_exists(&path.join(".git")) {
+                    info!(
+                        message = "Repository already cloned, skipping",
+                        path = ?path
+                    );
+                    return Ok(());
+                }
+
+                if let Some(parent_dir) = path.parent() {
+                    file_access.create_dir_all(parent_dir)?;
+                }
+
+                let mut arguments = vec!["clone".into()];
+                if let Some(branch) = branch {
+                    arguments.extend(["--branch".into(), branch.clone()]);
+                }
+                arguments.extend([url.clone(), path_to_string(path)]);
+
+                run_git(system, executor, arguments, None)
+            }
+            Self::Pull { path } => run_git(system, executor, vec!["pull".into()], Some(path)),
+            Self::Checkout { path, branch } => run_git(
+                system,
+                executor,
+                vec!["checkout".into(), branch.clone()],
+                Some(path),
+            ),
+            Self::WorktreeAdd {
+                path,
+                worktree_path,
+                branch,
+            } => {
+                if file_access.file_exists(worktree_path) {
+                    info!(
+                        message = "Worktree already exists, skipping",
+                        worktree_path = ?worktree_path
+                    );
+                    return Ok(());
+                }
+
+                let mut arguments = vec![
+                    "worktree".into(),
+                    "add".into(),
+                    path_to_string(worktree_path),
+                ];
+                arguments.extend(branch.clone());
+
+                run_git(system, executor, arguments, Some(path))
+            }
+        }
+    }
+
+    pub(crate) fn to_pretty_string(&self) -> String {
+        match self {
+            Self::EnsureClone { url, path, branch } => {
+                let mut output = format!("Clone \"{url}\" into {path:?} if missing");
+                if let Some(branch) = branch {
+                    output.push_str(&format!(" on branch \"{branch}\""));
+                }
+
+                output
+            }
+            Self::Pull { path } => format!("Pull Git repository {path:?}"),
+            Self::Checkout { path, branch } => {
+                format!("Checkout branch \"{branch}\" in Git repository {path:?}")
+            }
+            Self::WorktreeAdd {
+                path,
+                worktree_path,
+                branch,
+            } => {
+                let mut output =
+                    format!("Add worktree {worktree_path:?} to Git repository {path:?}");
+                if let Some(branch) = branch {
+                    output.push_str(&format!(" for branch \"{branch}\""));
+                }
+
+                output
+            }
+        }
+    }
+}
+
+impl Resolvable for ActionGit {
+    fn resolve(&mut self, ctx: &Context) {
+        match self {
+            Self::EnsureClone { url, path, branch } => {
+                url.resolve(ctx);
+                path.resolve(ctx);
+                branch.resolve(ctx);
+            }
+            Self::Pull { path } => {
+                path.resolve(ctx);
+            }
+            Self::Checkout { path, branch } => {
+                path.resolve(ctx);
+                branch.resolve(ctx);
+            }
+            Self::WorktreeAdd {
+                path,
+                worktree_path,
+                branch,
+            } => {
+                path.resolve(ctx);
+                worktree_path.resolve(ctx);
+                branch.resolve(ctx);
+            }
+        }
+    }
+}
+
+/// Run a Git command, failing with its error output when it exits with an error.
+fn run_git<E: CommandExecutor>(
+    system: &System,
+    executor: &E,
+    arguments: Vec<String>,
+    working_directory: Option<&Path>,
+) -> Result<()> {
+    let command_line = arguments.join(" ");
+    let output = executor.capture(system.run_git(arguments, working_directory))?;
+    if !output.success {
+        bail!("'git {command_line}' failed: {}", output.stderr.trim());
+    }
+
+    Ok(())
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        intent::{CommandCreationType, CommandIntent},
+        testing::{MemoryFileAccess, RecordingExecutor},
+        CommandExecutorIo, FileAccessIo,
+    };
+
+    fn clone_action(branch: Option<&str>) -> ActionGit {
+        ActionGit::EnsureClone {
+            url: "https://example.com/repo.git".into(),
+            path: "/code/repo".into(),
+            branch: branch.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn ensure_clone_clones_missing_repositories() {
+        let executor = RecordingExecutor::default();
+        let fs = MemoryFileAccess::default();
+
+        clone_action(Some("main"))
+            .execute(&System::default(), &executor, &fs)
+            .unwrap();
+
+        assert_eq!(
+            executor.commands(),
+            ["git clone --branch main https://example.com/repo.git /code/repo"]
+        );
+        assert!(fs.is_dir(Path::new("/code")));
+    }
+
+    #[test]
+    fn ensure_clone_skips_existing_repositories() {
+        let executor = RecordingExecutor::default();
+        let fs = MemoryFileAccess::default().with_dir("/code/repo/.git");
+
+        clone_action(None)
+            .execute(&System::default(), &executor, &fs)
+            .unwrap();
+
+        assert!(executor.commands().is_empty());
+    }
+
+    #[test]
+    fn failed_git_commands_are_errors() {
+        let executor = RecordingExecutor::default().failing_on("git checkout");
+        let fs = MemoryFileAccess::default();
+        let action = ActionGit::Checkout {
+            path: "/code/repo".into(),
+            branch: "unknown".into(),
+        };
+
+        assert!(action.execute(&System::default(), &executor, &fs).is_err());
+    }
+
+    #[test]
+    fn git_commands_wait_in_the_repository() {
+        let executor = RecordingExecutor::default();
+        let fs = MemoryFileAccess::default();
+
+        ActionGit::Pull {
+            path: "/code/repo".into(),
+        }
+        .execute(&System::default(), &executor, &fs)
+        .unwrap();
+
+        let intents = executor.intents.borrow();
+        let CommandIntent::Custom {
+            working_directory,
+            creation_type,
+            ..
+        } = &intents[0]
+        else {
+            panic!("Expected a custom command");
+        };
+        assert_eq!(working_directory.as_deref(), Some(Path::new("/code/repo")));
+        assert!(matches!(creation_type, CommandCreationType::Wait));
+    }
+
+    #[test]
+    fn worktree_add_skips_existing_worktrees() {
+        let executor = RecordingExecutor::default();
+        let fs = MemoryFileAccess::default().with_dir("/code/repo-feature");
+        let action = ActionGit::WorktreeAdd {
+            path: "/code/repo".into(),
+            worktree_path: "/code/repo-feature".into(),
+            branch: Some("feature".into()),
+        };
+
+        action.execute(&System::default(), &executor, &fs).unwrap();
+        assert!(executor.commands().is_empty());
+
+        fs.remove_dir_all(Path::new("/code/repo-feature")).unwrap();
+        action.execute(&System::default(), &executor, &fs).unwrap();
+        assert_eq!(
+            executor.commands(),
+            ["git worktree add /code/repo-feature feature"]
+        );
+    }
+
+    /// Run a real Git command in a folder, for the test fixtures.
+    fn git(directory: &Path, arguments: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=qx", "-c", "user.email=qx@localhost"])
+            .args(["-c", "commit.gpgsign=false"])
+            .args(arguments)
+            .current_dir(directory)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {arguments:?} failed");
+    }
+
+    #[test]
+    fn git_actions_work_on_local_bare_repositories() {
+        let root = std::env::temp_dir().join(format!("qx-git-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("source")).unwrap();
+
+        git(&root.join("source"), &["init", "-q", "-b", "main"]);
+        git(
+            &root.join("source"),
+            &["commit", "-q", "--allow-empty", "-m", "init"],
+        );
+        git(&root.join("source"), &["branch", "feature"]);
+        git(&root, &["clone", "-q", "--bare", "source", "remote.git"]);
+
+        let system = System::default();
+        let executor = CommandExecutorIo::default();
+        let fs = FileAccessIo::default();
+        let clone = root.join("clone");
+
+        let action = ActionGit::EnsureClone {
+            url: path_to_string(&root.join("remote.git")),
+            path: clone.clone(),
+            branch: None,
+        };
+        action.execute(&system, &executor, &fs).unwrap();
+        assert!(clone.join(".git").is_dir());
+        action.execute(&system, &executor, &fs).unwrap();
+
+        ActionGit::Checkout {
+            path: clone.clone(),
+            branch: "feature".into(),
+        }
+        .execute(&system, &executor, &fs)
+        .unwrap();
+        let head = std::fs::read_to_string(clone.join(".git/HEAD")).unwrap();
+        assert_eq!(head.trim(), "ref: refs/heads/feature");
+
+        let error = ActionGit::Checkout {
+            path: clone.clone(),
+            branch: "unknown".into(),
+        }
+        .execute(&system, &executor, &fs)
+        .unwrap_err();
+        assert!(error.to_string().contains("'git checkout unknown' failed"));
+
+        ActionGit::WorktreeAdd {
+            path: clone.clone(),
+            worktree_path: root.join("clone-main"),
+            branch: Some("main".into()),
+        }
+        .execute(&system, &executor, &fs)
+        .unwrap();
+        assert!(root.join("clone-main/.git").is_file());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
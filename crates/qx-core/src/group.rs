@@ -0,0 +1,93 @@
+use std::{collections::HashMap, fmt::Write, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::Action, context::Context, intent::CommandCreationType, resolvable::Resolvable,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    #[default]
+    Stop,
+    Continue,
+}
+
+/// A named list of actions sharing defaults.
+///
+/// The working directory and environment only apply to the commands started by
+/// `run` and `spawn_shell` actions, the other actions use their settings as written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionGroup {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_type: Option<CommandCreationType>,
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+    pub actions: Vec<Action>,
+}
+
+/// Defaults for the actions of a group, applied to actions which do not set them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActionDefaults {
+    pub working_directory: Option<PathBuf>,
+    pub creation_type: Option<CommandCreationType>,
+}
+
+impl ActionDefaults {
+    /// Override the current defaults with the ones of a group.
+    pub fn merged(&self, group: &ActionGroup) -> Self {
+        Self {
+            working_directory: group
+                .working_directory
+                .clone()
+                .or_else(|| self.working_directory.clone()),
+            creation_type: group
+                .creation_type
+                .clone()
+                .or_else(|| self.creation_type.clone()),
+        }
+    }
+}
+
+impl ActionGroup {
+    pub fn to_pretty_string(&self) -> String {
+        let mut output = format!("Group \"{}\"", self.name);
+
+        if let Some(cwd) = &self.working_directory {
+            write!(output, " with working directory {cwd:?}").unwrap();
+        }
+
+        if !self.env.is_empty() {
+            let mut names: Vec<_> = self.env.keys().cloned().collect();
+            names.sort();
+            write!(output, " with environment [{}]", names.join(", ")).unwrap();
+        }
+
+        if let Some(creation_type) = &self.creation_type {
+            write!(output, " using creation type \"{creation_type:?}\"").unwrap();
+        }
+
+        if let FailurePolicy::Continue = self.on_failure {
+            write!(output, " continuing on failure").unwrap();
+        }
+
+        output
+    }
+}
+
+impl Resolvable for ActionGroup {
+    fn resolve(&mut self, ctx: &Context) {
+        // Nested actions are resolved just before their execution.
+        self.name.resolve(ctx);
+        self.working_directory.resolve(ctx);
+        for value in self.env.values_mut() {
+            value.resolve(ctx);
+        }
+    }
+}
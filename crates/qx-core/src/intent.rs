@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -20,12 +20,22 @@ impl CommandCreationType {
 pub enum CommandIntent {
     System {
         target: PathBuf,
+        application: Option<String>,
         creation_type: CommandCreationType,
     },
     Custom {
         target: PathBuf,
         arguments: Vec<String>,
         working_directory: Option<PathBuf>,
+        input: Option<String>,
+        environment: HashMap<String, String>,
         creation_type: CommandCreationType,
     },
 }
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
@@ -1,11 +1,75 @@
-use std::process::{Command, Stdio};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, SystemTime};
 
-use color_eyre::Result;
+use color_eyre::{
+    eyre::bail,
+    owo_colors::{OwoColorize, Style},
+    Result,
+};
 
-use crate::intent::{CommandCreationType, CommandIntent};
+use crate::{
+    checklist::parse_checklist_selection,
+    intent::{CommandCreationType, CommandIntent, CommandOutput},
+    message::{parse_message, MessageLevel, MessageLine, MessageSpan},
+};
+
+/// A file opened for writing, which can seek as zip archives need it.
+pub trait WriteSeek: Write + Seek {}
+
+impl<T: Write + Seek> WriteSeek for T {}
+
+pub trait FileAccess: Clone + 'static {
+    fn user_data_directory(&self) -> PathBuf;
+    fn read_to_string(&self, path: &Path) -> Result<String>;
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+    /// Open a file for streaming reads, without loading it in memory.
+    fn open(&self, path: &Path) -> Result<Box<dyn Read>>;
+    fn write<D: AsRef<[u8]>>(&self, path: &Path, data: D) -> Result<()>;
+    /// Create or truncate a file for streaming writes, which are only
+    /// guaranteed to be stored once flushed.
+    fn create(&self, path: &Path) -> Result<Box<dyn WriteSeek>>;
+    /// Create an empty file, returning `false` if it already exists.
+    fn create_new(&self, path: &Path) -> Result<bool>;
+    fn append<D: AsRef<[u8]>>(&self, path: &Path, data: D) -> Result<()>;
+    fn create_dir_all(&self, path: &Path) -> Result<()>;
+    fn file_exists(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn is_symlink(&self, path: &Path) -> bool;
+    fn modified(&self, path: &Path) -> Result<SystemTime>;
+    fn file_size(&self, path: &Path) -> Result<u64>;
+    /// Unix permission bits of a file, `0o644` on platforms without them.
+    fn permissions(&self, path: &Path) -> Result<u32>;
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
+    fn copy_file(&self, source: &Path, target: &Path) -> Result<()>;
+    fn rename(&self, source: &Path, target: &Path) -> Result<()>;
+    fn symlink(&self, source: &Path, target: &Path) -> Result<()>;
+    fn remove_file(&self, path: &Path) -> Result<()>;
+    fn remove_dir(&self, path: &Path) -> Result<()>;
+    fn remove_dir_all(&self, path: &Path) -> Result<()>;
+}
+
+pub trait Console {
+    fn confirm(&self, message: &str) -> Result<bool>;
+
+    /// Show a markdown-lite message, and wait for the user if `pause` is set.
+    fn message(&self, level: &MessageLevel, message: &str, pause: bool) -> Result<()>;
+
+    /// Show a checklist and return which items were acknowledged.
+    /// When `blocking` is set, only return once every item is acknowledged.
+    fn checklist(&self, title: &str, items: &[String], blocking: bool) -> Result<Vec<bool>>;
+}
+
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+    fn sleep(&self, duration: Duration);
+}
 
 pub trait CommandExecutor {
     fn execute(&self, intent: CommandIntent) -> Result<()>;
+    fn capture(&self, intent: CommandIntent) -> Result<CommandOutput>;
 }
 
 #[derive(Default)]
@@ -18,44 +82,474 @@ impl CommandExecutor for CommandExecutorIo {
                 target,
                 arguments,
                 working_directory,
+                input,
+                environment,
                 creation_type,
             } => {
                 let mut command = Command::new(target);
-                command.args(&arguments);
+                command.args(&arguments).envs(&environment);
 
                 if let Some(dir) = working_directory {
                     command.current_dir(dir);
                 }
 
+                if input.is_some() {
+                    command.stdin(Stdio::piped());
+                }
+
                 match creation_type {
                     CommandCreationType::Detach => {
-                        command
+                        if input.is_none() {
+                            command.stdin(Stdio::null());
+                        }
+
+                        Self::detach(&mut command);
+
+                        let mut child = command
                             .stdout(Stdio::null())
-                            .stdin(Stdio::null())
                             .stderr(Stdio::null())
                             .spawn()?;
+                        Self::write_input(&mut child, input)?;
                     }
                     CommandCreationType::DetachWithOutput => {
-                        command.spawn()?;
+                        let mut child = command.spawn()?;
+                        Self::write_input(&mut child, input)?;
                     }
                     CommandCreationType::Wait => {
-                        command.status()?;
+                        let mut child = command.spawn()?;
+                        Self::write_input(&mut child, input)?;
+                        child.wait()?;
                     }
                 }
             }
             CommandIntent::System {
                 target,
+                application,
                 creation_type,
-            } => match creation_type {
-                CommandCreationType::Detach => {
+            } => match (application, creation_type) {
+                (None, CommandCreationType::Detach) => {
                     open::that_detached(target)?;
                 }
-                CommandCreationType::DetachWithOutput | CommandCreationType::Wait => {
+                (None, CommandCreationType::DetachWithOutput | CommandCreationType::Wait) => {
                     open::that(target)?;
                 }
+                (Some(application), CommandCreationType::Detach) => {
+                    open::with_detached(target, application)?;
+                }
+                (
+                    Some(application),
+                    CommandCreationType::DetachWithOutput | CommandCreationType::Wait,
+                ) => {
+                    open::with(target, application)?;
+                }
             },
         }
 
         Ok(())
     }
+
+    fn capture(&self, intent: CommandIntent) -> Result<CommandOutput> {
+        let CommandIntent::Custom {
+            target,
+            arguments,
+            working_directory,
+            input,
+            environment,
+            ..
+        } = intent
+        else {
+            bail!("Cannot capture the output of a system command")
+        };
+
+        let mut command = Command::new(target);
+        command
+            .args(&arguments)
+            .envs(&environment)
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if let Some(dir) = working_directory {
+            command.current_dir(dir);
+        }
+
+        let mut child = command.spawn()?;
+        Self::write_input(&mut child, input)?;
+        let output = child.wait_with_output()?;
+
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
+impl CommandExecutorIo {
+    /// Start the command outside of the terminal session, so it survives
+    /// the terminal closing and does not receive its Ctrl+C.
+    fn detach(command: &mut Command) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const DETACHED_PROCESS: u32 = 0x0000_0008;
+            const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+            command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+        }
+    }
+
+    fn write_input(child: &mut Child, input: Option<String>) -> Result<()> {
+        // Stdin is dropped once written, so the child sees EOF.
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct FileAccessIo {}
+
+impl FileAccess for FileAccessIo {
+    fn user_data_directory(&self) -> PathBuf {
+        dirs::data_dir().expect("Could not retrieve data directory.")
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        std::fs::read_to_string(path).map_err(Into::into)
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        std::fs::read(path).map_err(Into::into)
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read>> {
+        Ok(Box::new(BufReader::new(std::fs::File::open(path)?)))
+    }
+
+    fn write<D: AsRef<[u8]>>(&self, path: &Path, data: D) -> Result<()> {
+        std::fs::write(path, data).map_err(Into::into)
+    }
+
+    fn create(&self, path: &Path) -> Result<Box<dyn WriteSeek>> {
+        Ok(Box::new(BufWriter::new(std::fs::File::create(path)?)))
+    }
+
+    fn create_new(&self, path: &Path) -> Result<bool> {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn append<D: AsRef<[u8]>>(&self, path: &Path, data: D) -> Result<()> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(data.as_ref())
+            .map_err(Into::into)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        std::fs::create_dir_all(path).map_err(Into::into)
+    }
+
+    fn file_exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        path.is_symlink()
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime> {
+        std::fs::metadata(path)?.modified().map_err(Into::into)
+    }
+
+    fn file_size(&self, path: &Path) -> Result<u64> {
+        Ok(std::fs::metadata(path)?.len())
+    }
+
+    #[cfg(unix)]
+    fn permissions(&self, path: &Path) -> Result<u32> {
+        use std::os::unix::fs::PermissionsExt;
+        Ok(std::fs::metadata(path)?.permissions().mode() & 0o7777)
+    }
+
+    #[cfg(not(unix))]
+    fn permissions(&self, path: &Path) -> Result<u32> {
+        std::fs::metadata(path)?;
+        Ok(0o644)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()).map_err(Into::into))
+            .collect()
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        std::fs::canonicalize(path).map_err(Into::into)
+    }
+
+    fn copy_file(&self, source: &Path, target: &Path) -> Result<()> {
+        std::fs::copy(source, target)?;
+        Ok(())
+    }
+
+    fn rename(&self, source: &Path, target: &Path) -> Result<()> {
+        std::fs::rename(source, target).map_err(Into::into)
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, source: &Path, target: &Path) -> Result<()> {
+        std::os::unix::fs::symlink(source, target).map_err(Into::into)
+    }
+
+    #[cfg(windows)]
+    fn symlink(&self, source: &Path, target: &Path) -> Result<()> {
+        if source.is_dir() {
+            std::os::windows::fs::symlink_dir(source, target).map_err(Into::into)
+        } else {
+            std::os::windows::fs::symlink_file(source, target).map_err(Into::into)
+        }
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        std::fs::remove_file(path).map_err(Into::into)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<()> {
+        std::fs::remove_dir(path).map_err(Into::into)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        std::fs::remove_dir_all(path).map_err(Into::into)
+    }
+}
+
+#[derive(Default)]
+pub struct ConsoleIo {}
+
+impl Console for ConsoleIo {
+    fn confirm(&self, message: &str) -> Result<bool> {
+        print!("{message} [y/N] ");
+        std::io::stdout().flush()?;
+
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer)?;
+
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    fn message(&self, level: &MessageLevel, message: &str, pause: bool) -> Result<()> {
+        let style = match level {
+            MessageLevel::Info => Style::new().blue(),
+            MessageLevel::Success => Style::new().green(),
+            MessageLevel::Warn => Style::new().yellow(),
+            MessageLevel::Error => Style::new().red(),
+        };
+
+        let tag = format!("[{}]", level.label());
+        let indent = " ".repeat(tag.len());
+        for (index, line) in parse_message(message).iter().enumerate() {
+            let prefix = if index == 0 {
+                tag.style(style.bold()).to_string()
+            } else {
+                indent.clone()
+            };
+
+            match line {
+                MessageLine::Text(spans) => println!("{prefix} {}", render_spans(spans)),
+                MessageLine::ListItem(spans) => println!("{prefix} • {}", render_spans(spans)),
+            }
+        }
+
+        if pause {
+            print!("Press Enter to continue...");
+            std::io::stdout().flush()?;
+            std::io::stdin().lock().read_line(&mut String::new())?;
+        }
+
+        Ok(())
+    }
+
+    fn checklist(&self, title: &str, items: &[String], blocking: bool) -> Result<Vec<bool>> {
+        run_checklist(
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+            title,
+            items,
+            blocking,
+        )
+    }
+}
+
+/// Show a numbered checklist, reading the acknowledged items from `input`.
+fn run_checklist<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    title: &str,
+    items: &[String],
+    blocking: bool,
+) -> Result<Vec<bool>> {
+    let mut acknowledged = vec![false; items.len()];
+
+    loop {
+        writeln!(output, "  > {title}")?;
+        for (index, (item, done)) in items.iter().zip(&acknowledged).enumerate() {
+            writeln!(
+                output,
+                "    {}. [{}] {item}",
+                index + 1,
+                if *done { "x" } else { " " }
+            )?;
+        }
+        writeln!(output)?;
+
+        if acknowledged.iter().all(|a| *a) {
+            return Ok(acknowledged);
+        }
+
+        write!(
+            output,
+            "Acknowledge items (numbers or ranges like 2-4, 'a' for all, empty to continue): "
+        )?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            if blocking {
+                bail!("Input closed before the checklist '{title}' was completed");
+            }
+            return Ok(acknowledged);
+        }
+
+        let answer = answer.trim();
+        if answer.is_empty() {
+            if !blocking {
+                return Ok(acknowledged);
+            }
+
+            writeln!(output, "Every item must be acknowledged to continue.")?;
+            writeln!(output)?;
+            continue;
+        }
+
+        if answer.eq_ignore_ascii_case("a") {
+            acknowledged.fill(true);
+            continue;
+        }
+
+        let (selected, unknown) = parse_checklist_selection(answer, items.len());
+        for index in selected {
+            acknowledged[index] = true;
+        }
+        for value in unknown {
+            writeln!(output, "Unknown item '{value}', ignoring.")?;
+        }
+        writeln!(output)?;
+    }
+}
+
+fn render_spans(spans: &[MessageSpan]) -> String {
+    spans
+        .iter()
+        .map(|span| {
+            let text = match &span.link {
+                Some(link) => format!("{} ({link})", span.text.underline()),
+                None => span.text.clone(),
+            };
+
+            if span.bold {
+                text.bold().to_string()
+            } else {
+                text
+            }
+        })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct ClockIo {}
+
+impl Clock for ClockIo {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checklist(answers: &str, blocking: bool) -> (Result<Vec<bool>>, String) {
+        let items = ["backup", "deploy", "tag"].map(String::from);
+        let mut output = vec![];
+
+        let result = run_checklist(
+            &mut answers.as_bytes(),
+            &mut output,
+            "Release",
+            &items,
+            blocking,
+        );
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn blocking_checklists_wait_for_every_item() {
+        let (result, output) = checklist("1\n\n9 2-3\n", true);
+
+        assert_eq!(result.unwrap(), [true, true, true]);
+        assert!(output.contains("Every item must be acknowledged to continue."));
+        assert!(output.contains("Unknown item '9', ignoring."));
+        assert!(output.ends_with("    3. [x] tag\n\n"));
+    }
+
+    #[test]
+    fn blocking_checklists_fail_when_the_input_closes() {
+        let (result, _) = checklist("1\n", true);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn checklists_continue_on_empty_answers() {
+        let (result, _) = checklist("2\n\n", false);
+
+        assert_eq!(result.unwrap(), [false, true, false]);
+    }
+
+    #[test]
+    fn checklists_acknowledge_every_item_with_a() {
+        let (result, _) = checklist("A\n", true);
+
+        assert_eq!(result.unwrap(), [true, true, true]);
+    }
 }
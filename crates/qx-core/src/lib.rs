@@ -1,19 +1,68 @@
 mod actions;
+mod background;
+mod backup;
+mod browser;
+mod checklist;
 mod configuration;
 mod context;
+mod dotenv;
+mod editor;
 mod environment;
+mod filesystem;
+mod foreach;
+mod git;
+mod group;
 mod intent;
 mod io;
+mod message;
+mod one_or_many;
+mod process;
+mod require;
 mod resolvable;
+mod script;
+mod shell;
 mod system;
+mod terminal;
+#[cfg(test)]
+mod testing;
+mod timer;
+mod tmux;
+mod vscode;
+mod watch;
 
-pub use actions::{Action, ActionContext};
+pub use actions::{Action, ActionContext, FileExistsPolicy};
+pub use background::BackgroundTask;
+pub use backup::{ActionBackup, BackupFormat};
+pub use browser::{BrowserKind, BrowserProfile};
+pub use checklist::{parse_checklist_items, ActionChecklist, ChecklistOutcome};
 pub use configuration::Configuration;
-pub use context::Context;
+pub use context::{Context, Variable};
+pub use dotenv::parse_dotenv;
+pub use editor::EditorProfile;
 pub use environment::Environment;
-pub use io::{CommandExecutor, CommandExecutorIo};
-pub use resolvable::Resolvable;
-pub use system::System;
+pub use foreach::ActionForeach;
+pub use git::ActionGit;
+pub use group::{ActionDefaults, ActionGroup, FailurePolicy};
+pub use intent::{CommandCreationType, CommandIntent, CommandOutput};
+pub use io::{
+    Clock, ClockIo, CommandExecutor, CommandExecutorIo, Console, ConsoleIo, FileAccess,
+    FileAccessIo, WriteSeek,
+};
+pub use message::{parse_message, MessageLevel, MessageLine, MessageSpan};
+pub use one_or_many::OneOrMany;
+pub use process::{
+    find_processes, kill_processes, parse_process_list, ProcessInfo, ProcessMatcher,
+};
+pub use require::{ActionRequire, RequiredExecutable, RequiredPath, RequiredVariable};
+pub use resolvable::{Resolvable, ResolvableClone};
+pub use script::ActionScript;
+pub use shell::{ShellChanges, ShellKind, ShellTarget};
+pub use system::{NotificationUrgency, System};
+pub use terminal::{TerminalPreset, TerminalProfile};
+pub use timer::{parse_duration, ActionTimer, TimerPhase, TimerPhaseKind};
+pub use tmux::{ActionTmux, TmuxPane, TmuxWindow};
+pub use vscode::{VSCodeRemote, VSCodeWindow};
+pub use watch::ActionWatch;
 
 const PROJECT_URL: &str = "https://github.com/Srynetix/qx";
 
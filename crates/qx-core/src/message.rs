@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageLevel {
+    #[default]
+    Info,
+    Success,
+    Warn,
+    Error,
+}
+
+impl MessageLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Success => "success",
+            Self::Warn => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageSpan {
+    pub text: String,
+    pub bold: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageLine {
+    Text(Vec<MessageSpan>),
+    ListItem(Vec<MessageSpan>),
+}
+
+/// Parse a markdown-lite message, supporting `**bold**` text,
+/// `[label](url)` links and `-` / `*` list items.
+pub fn parse_message(message: &str) -> Vec<MessageLine> {
+    message
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            match trimmed
+                .strip_prefix("- ")
+                .or_else(|| trimmed.strip_prefix("* "))
+            {
+                Some(item) => MessageLine::ListItem(parse_spans(item)),
+                None => MessageLine::Text(parse_spans(line)),
+            }
+        })
+        .collect()
+}
+
+fn parse_spans(line: &str) -> Vec<MessageSpan> {
+    let mut spans = vec![];
+    let mut text = String::new();
+    let mut bold = false;
+    let mut rest = line;
+
+    let flush = |text: &mut String, bold: bool, spans: &mut Vec<MessageSpan>| {
+        if !text.is_empty() {
+            spans.push(MessageSpan {
+                text: std::mem::take(text),
+                bold,
+                link: None,
+            });
+        }
+    };
+
+    while let Some(c) = rest.chars().next() {
+        // An unclosed `**` is kept as text.
+        if let Some(after) = rest.strip_prefix("**").filter(|a| bold || a.contains("**")) {
+            flush(&mut text, bold, &mut spans);
+            bold = !bold;
+            rest = after;
+            continue;
+        }
+
+        if c == '[' {
+            if let Some((label, url, after)) = parse_link(rest) {
+                flush(&mut text, bold, &mut spans);
+                spans.push(MessageSpan {
+                    text: label.to_string(),
+                    bold,
+                    link: Some(url.to_string()),
+                });
+                rest = after;
+                continue;
+            }
+        }
+
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    flush(&mut text, bold, &mut spans);
+    spans
+}
+
+/// Parse a `[label](url)` link, returning the label, the URL and the remaining text.
+fn parse_link(value: &str) -> Option<(&str, &str, &str)> {
+    let (label, rest) = value.strip_prefix('[')?.split_once("](")?;
+    let (url, rest) = rest.split_once(')')?;
+
+    Some((label, url, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, bold: bool, link: Option<&str>) -> MessageSpan {
+        MessageSpan {
+            text: text.into(),
+            bold,
+            link: link.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn bold_text_can_hold_links() {
+        assert_eq!(
+            parse_message("Run **now, see [docs](https://qx.dev)** please"),
+            [MessageLine::Text(vec![
+                span("Run ", false, None),
+                span("now, see ", true, None),
+                span("docs", true, Some("https://qx.dev")),
+                span(" please", false, None),
+            ])]
+        );
+    }
+
+    #[test]
+    fn unclosed_bold_markers_are_text() {
+        assert_eq!(
+            parse_message("**a** and 2**3"),
+            [MessageLine::Text(vec![
+                span("a", true, None),
+                span(" and 2**3", false, None),
+            ])]
+        );
+    }
+
+    #[test]
+    fn incomplete_links_are_text() {
+        assert_eq!(
+            parse_message("[docs](https://qx.dev and [x]"),
+            [MessageLine::Text(vec![span(
+                "[docs](https://qx.dev and [x]",
+                false,
+                None
+            )])]
+        );
+    }
+
+    #[test]
+    fn list_items() {
+        assert_eq!(
+            parse_message("Steps:\n  - **build**\n* [deploy](https://ci)\n-not an item"),
+            [
+                MessageLine::Text(vec![span("Steps:", false, None)]),
+                MessageLine::ListItem(vec![span("build", true, None)]),
+                MessageLine::ListItem(vec![span("deploy", false, Some("https://ci"))]),
+                MessageLine::Text(vec![span("-not an item", false, None)]),
+            ]
+        );
+    }
+}
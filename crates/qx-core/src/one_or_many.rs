@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{context::Context, resolvable::Resolvable};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            Self::One(value) => std::slice::from_ref(value),
+            Self::Many(values) => values,
+        }
+    }
+}
+
+impl<T: Resolvable> Resolvable for OneOrMany<T> {
+    fn resolve(&mut self, ctx: &Context) {
+        match self {
+            Self::One(value) => value.resolve(ctx),
+            Self::Many(values) => values.resolve(ctx),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::{path::Path, time::Duration};
+
+use color_eyre::{eyre::bail, Result};
+use regex::Regex;
+use tracing::info;
+
+use crate::{Clock, CommandExecutor, System};
+
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub command_line: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessMatcher {
+    pub name: Option<String>,
+    pub command_line: Option<Regex>,
+    pub pids: Option<Vec<u32>>,
+}
+
+impl ProcessMatcher {
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        if process.pid == std::process::id() {
+            return false;
+        }
+
+        let name_matches = self.name.as_ref().map_or(true, |name| {
+            let name = name.to_lowercase();
+            let process_name = process.name.to_lowercase();
+            process_name == name || process_name.trim_end_matches(".exe") == name
+        });
+        let command_line_matches = self
+            .command_line
+            .as_ref()
+            .map_or(true, |rgx| rgx.is_match(&process.command_line));
+        let pid_matches = self
+            .pids
+            .as_ref()
+            .map_or(true, |pids| pids.contains(&process.pid));
+
+        name_matches && command_line_matches && pid_matches
+    }
+
+    /// Match processes running a target with the given arguments.
+    ///
+    /// The target is searched in the command line rather than compared to the
+    /// process name, so paths with spaces and scripts started through an
+    /// interpreter (`node server.js`, `python app.py`) are found.
+    pub fn for_command(target: &Path, args: &[String]) -> Result<Self> {
+        let file_name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut pattern = format!(
+            r#"(?:^|[\\/\s"]){}(?:\.exe)?(?:[\s"]|$)"#,
+            regex::escape(&file_name)
+        );
+        for arg in args {
+            pattern.push_str(".*");
+            pattern.push_str(&regex::escape(arg));
+        }
+
+        Ok(Self {
+            command_line: Some(Regex::new(&pattern)?),
+            ..Default::default()
+        })
+    }
+}
+
+/// Parse the output of [`System::list_processes`], one `pid<TAB>name<TAB>command line`
+/// (Windows) or `pid command line` (other platforms) entry per line.
+pub fn parse_process_list(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.contains('\t') {
+                let mut parts = line.splitn(3, '\t');
+                let pid = parts.next()?.trim().parse().ok()?;
+                let name = parts.next()?.trim().to_string();
+                let command_line = parts.next().unwrap_or_default().trim().to_string();
+
+                Some(ProcessInfo {
+                    pid,
+                    name,
+                    command_line,
+                })
+            } else {
+                let (pid, command_line) = line.split_once(' ')?;
+                let command_line = command_line.trim().to_string();
+                let executable = command_line.split_whitespace().next().unwrap_or_default();
+                let name = Path::new(executable)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                Some(ProcessInfo {
+                    pid: pid.parse().ok()?,
+                    name,
+                    command_line,
+                })
+            }
+        })
+        .collect()
+}
+
+pub fn find_processes<E: CommandExecutor>(
+    system: &System,
+    executor: &E,
+    matcher: &ProcessMatcher,
+) -> Result<Vec<ProcessInfo>> {
+    let output = executor.capture(system.list_processes())?;
+    if !output.success {
+        bail!("Could not list processes: {}", output.stderr.trim());
+    }
+
+    Ok(parse_process_list(&output.stdout)
+        .into_iter()
+        .filter(|p| matcher.matches(p))
+        .collect())
+}
+
+pub fn kill_processes<E: CommandExecutor, K: Clock>(
+    system: &System,
+    executor: &E,
+    clock: &K,
+    matcher: &ProcessMatcher,
+    grace_period: Duration,
+) -> Result<()> {
+    let processes = find_processes(system, executor, matcher)?;
+    if processes.is_empty() {
+        info!(message = "No matching process to kill");
+        return Ok(());
+    }
+
+    let pids: Vec<_> = processes.iter().map(|p| p.pid).collect();
+    info!(
+        message = "Terminating processes",
+        pids = ?pids
+    );
+    signal_processes(system, executor, &pids, false)?;
+
+    let deadline = clock.now() + grace_period;
+    loop {
+        let remaining = running_pids(system, executor, &pids)?;
+        if remaining.is_empty() {
+            return Ok(());
+        }
+
+        if clock.now() >= deadline {
+            info!(
+                message = "Grace period expired, killing processes",
+                pids = ?remaining
+            );
+            return signal_processes(system, executor, &remaining, true);
+        }
+
+        clock.sleep(Duration::from_millis(250));
+    }
+}
+
+fn running_pids<E: CommandExecutor>(
+    system: &System,
+    executor: &E,
+    pids: &[u32],
+) -> Result<Vec<u32>> {
+    let matcher = ProcessMatcher {
+        pids: Some(pids.to_vec()),
+        ..Default::default()
+    };
+
+    Ok(find_processes(system, executor, &matcher)?
+        .iter()
+        .map(|p| p.pid)
+        .collect())
+}
+
+fn signal_processes<E: CommandExecutor>(
+    system: &System,
+    executor: &E,
+    pids: &[u32],
+    force: bool,
+) -> Result<()> {
+    let output = executor.capture(system.terminate_processes(pids, force))?;
+    if output.success {
+        return Ok(());
+    }
+
+    // The kill command also fails when a process exited in the meantime.
+    let remaining = running_pids(system, executor, pids)?;
+    if !remaining.is_empty() {
+        bail!(
+            "Could not terminate processes {:?}: {}",
+            remaining,
+            output.stderr.trim()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeClock, RecordingExecutor};
+
+    fn process(command_line: &str) -> ProcessInfo {
+        parse_process_list(&format!("4242 {command_line}")).remove(0)
+    }
+
+    fn matcher(target: &str, args: &[&str]) -> ProcessMatcher {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        ProcessMatcher::for_command(Path::new(target), &args).unwrap()
+    }
+
+    #[test]
+    fn for_command_matches_paths_with_spaces() {
+        let matcher = matcher("/opt/My App/app", &["--port", "80"]);
+
+        assert!(matcher.matches(&process("/opt/My App/app --port 80")));
+        assert!(!matcher.matches(&process("/opt/My App/app --port 81")));
+        assert!(!matcher.matches(&process("/opt/My App/happ --port 80")));
+    }
+
+    #[test]
+    fn for_command_matches_interpreted_scripts() {
+        let matcher = matcher("./server.js", &[]);
+
+        assert!(matcher.matches(&process("node ./server.js")));
+        assert!(matcher.matches(&process("/usr/bin/node /srv/server.js --watch")));
+        assert!(!matcher.matches(&process("node ./server.json")));
+    }
+
+    #[test]
+    fn for_command_matches_windows_command_lines() {
+        let matcher = matcher("code", &[]);
+        let process =
+            parse_process_list("12\tCode.exe\t\"C:\\Program Files\\code.exe\" .").remove(0);
+
+        assert!(matcher.matches(&process));
+    }
+
+    #[test]
+    fn kill_processes_forces_after_the_grace_period() {
+        let executor = RecordingExecutor::default()
+            .with_output(true, "10 server\n11 server\n")
+            .with_output(true, "")
+            .with_output(true, "11 server\n")
+            .with_output(true, "11 server\n")
+            .with_output(true, "11 server\n")
+            .with_output(true, "11 server\n")
+            .with_output(true, "11 server\n");
+        let clock = FakeClock::default();
+
+        kill_processes(
+            &System::default(),
+            &executor,
+            &clock,
+            &ProcessMatcher::default(),
+            Duration::from_secs(1),
+        )
+        .unwrap();
+
+        let commands = executor.commands();
+        assert!(commands.contains(&"kill -TERM 10 11".to_string()));
+        assert_eq!(commands.last().unwrap(), "kill -KILL 11");
+        assert_eq!(clock.elapsed(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn kill_processes_fails_when_processes_survive_the_kill_command() {
+        let executor = RecordingExecutor::default()
+            .with_output(true, "10 server\n")
+            .with_output(false, "")
+            .with_output(true, "10 server\n");
+
+        let result = kill_processes(
+            &System::default(),
+            &executor,
+            &FakeClock::default(),
+            &ProcessMatcher::default(),
+            Duration::from_secs(1),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn kill_processes_ignores_processes_exiting_before_the_kill_command() {
+        let executor = RecordingExecutor::default()
+            .with_output(true, "10 server\n")
+            .with_output(false, "")
+            .with_output(true, "");
+
+        kill_processes(
+            &System::default(),
+            &executor,
+            &FakeClock::default(),
+            &ProcessMatcher::default(),
+            Duration::from_secs(1),
+        )
+        .unwrap();
+    }
+}
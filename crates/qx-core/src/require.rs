@@ -0,0 +1,299 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use color_eyre::{eyre::bail, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    context::Context,
+    intent::{CommandCreationType, CommandIntent},
+    resolvable::Resolvable,
+    CommandExecutor, FileAccess,
+};
+
+const DEFAULT_VERSION_PATTERN: &str = r"(\d+(?:\.\d+)+)";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequiredExecutable {
+    pub name: String,
+    pub min_version: Option<String>,
+    #[serde(default = "RequiredExecutable::default_version_arguments")]
+    pub version_arguments: Vec<String>,
+    pub version_pattern: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl RequiredExecutable {
+    fn default_version_arguments() -> Vec<String> {
+        vec!["--version".into()]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequiredVariable {
+    pub name: String,
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequiredPath {
+    pub path: PathBuf,
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionRequire {
+    #[serde(default)]
+    pub executables: Vec<RequiredExecutable>,
+    #[serde(default)]
+    pub env_vars: Vec<RequiredVariable>,
+    #[serde(default)]
+    pub paths: Vec<RequiredPath>,
+}
+
+impl ActionRequire {
+    pub fn to_pretty_string(&self) -> String {
+        let mut requirements = vec![];
+        requirements.extend(self.executables.iter().map(|e| match &e.min_version {
+            Some(version) => format!("{} {version}+", e.name),
+            None => e.name.clone(),
+        }));
+        requirements.extend(self.env_vars.iter().map(|v| format!("${}", v.name)));
+        requirements.extend(self.paths.iter().map(|p| format!("{:?}", p.path)));
+
+        format!("Check requirements: {}", requirements.join(", "))
+    }
+
+    /// Check every requirement, and fail with a list of the missing ones.
+    ///
+    /// Variables can come from the process environment, the context or the
+    /// variables exported by previous actions.
+    pub fn check<E: CommandExecutor, F: FileAccess>(
+        &self,
+        executor: &E,
+        file_access: &F,
+        context: &Context,
+        exported_variables: &HashMap<String, String>,
+    ) -> Result<()> {
+        let mut failures = vec![];
+
+        for executable in &self.executables {
+            if let Some(failure) = Self::check_executable(executor, executable)? {
+                failures.push((failure, &executable.hint));
+            }
+        }
+
+        for variable in &self.env_vars {
+            let is_set = context.get(&variable.name).is_some()
+                || context.get_list(&variable.name).is_some()
+                || exported_variables.contains_key(&variable.name)
+                || std::env::var_os(&variable.name).is_some();
+            if !is_set {
+                failures.push((
+                    format!("environment variable {} is not set", variable.name),
+                    &variable.hint,
+                ));
+            }
+        }
+
+        for path in &self.paths {
+            if !file_access.file_exists(&path.path) {
+                failures.push((format!("path {:?} does not exist", path.path), &path.hint));
+            }
+        }
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let lines = failures
+            .into_iter()
+            .map(|(failure, hint)| match hint {
+                Some(hint) => format!("  - {failure}\n    hint: {hint}"),
+                None => format!("  - {failure}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        bail!("Some requirements are not met:\n{lines}")
+    }
+
+    fn check_executable<E: CommandExecutor>(
+        executor: &E,
+        executable: &RequiredExecutable,
+    ) -> Result<Option<String>> {
+        info!(
+            message = "Checking executable",
+            name = ?executable.name
+        );
+
+        let intent = CommandIntent::Custom {
+            target: executable.name.clone().into(),
+            arguments: executable.version_arguments.clone(),
+            working_directory: None,
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Wait,
+        };
+
+        let Ok(output) = executor.capture(intent) else {
+            return Ok(Some(format!("{} is not installed", executable.name)));
+        };
+
+        // Starting is enough without a minimum version, as some tools do not
+        // support the version arguments and exit with an error.
+        let Some(min_version) = &executable.min_version else {
+            return Ok(None);
+        };
+
+        if !output.success {
+            return Ok(Some(format!(
+                "{} is not installed correctly, '{} {}' failed",
+                executable.name,
+                executable.name,
+                executable.version_arguments.join(" ")
+            )));
+        }
+
+        let pattern = Regex::new(
+            executable
+                .version_pattern
+                .as_deref()
+                .unwrap_or(DEFAULT_VERSION_PATTERN),
+        )?;
+        let text = format!("{}\n{}", output.stdout, output.stderr);
+        let version = pattern
+            .captures(&text)
+            .and_then(|c| c.get(1).or_else(|| c.get(0)))
+            .map(|m| m.as_str().to_string());
+
+        Ok(match version {
+            None => Some(format!(
+                "could not find the version of {} ({min_version} or later is required)",
+                executable.name
+            )),
+            Some(version) if compare_versions(&version, min_version).is_lt() => Some(format!(
+                "{} {version} is installed, {min_version} or later is required",
+                executable.name
+            )),
+            Some(_) => None,
+        })
+    }
+}
+
+/// Compare two dotted versions, missing components counting as zero.
+fn compare_versions(left: &str, right: &str) -> std::cmp::Ordering {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split(['.', '-', '+'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+
+    let (left, right) = (parse(left), parse(right));
+    let length = left.len().max(right.len());
+    let component = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+
+    (0..length)
+        .map(|i| component(&left, i).cmp(&component(&right, i)))
+        .find(|o| o.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+impl Resolvable for ActionRequire {
+    fn resolve(&mut self, ctx: &Context) {
+        for executable in &mut self.executables {
+            executable.name.resolve(ctx);
+            executable.min_version.resolve(ctx);
+            executable.hint.resolve(ctx);
+        }
+
+        for variable in &mut self.env_vars {
+            variable.name.resolve(ctx);
+            variable.hint.resolve(ctx);
+        }
+
+        for path in &mut self.paths {
+            path.path.resolve(ctx);
+            path.hint.resolve(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MemoryFileAccess, RecordingExecutor};
+
+    fn check(action: &str, executor: &RecordingExecutor, context: &Context) -> Result<()> {
+        let action: ActionRequire = serde_yaml::from_str(action).unwrap();
+        let exported = HashMap::from([("EXPORTED_TOKEN".to_string(), "1".to_string())]);
+
+        action.check(executor, &MemoryFileAccess::default(), context, &exported)
+    }
+
+    #[test]
+    fn check_finds_variables_in_the_context_and_exports() {
+        let context = Context::new([("project".to_string(), "qx".to_string())].into());
+
+        check(
+            "env_vars: [{name: project}, {name: EXPORTED_TOKEN}]",
+            &RecordingExecutor::default(),
+            &context,
+        )
+        .unwrap();
+
+        let error = check(
+            "env_vars: [{name: QX_MISSING_VARIABLE}]",
+            &RecordingExecutor::default(),
+            &context,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("QX_MISSING_VARIABLE is not set"));
+    }
+
+    #[test]
+    fn check_rejects_failing_version_commands() {
+        let executor = RecordingExecutor::default().with_output(false, "node 20.1.0");
+
+        let error = check(
+            "executables: [{name: node, min_version: '18'}]",
+            &executor,
+            &Context::empty(),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("'node --version' failed"));
+    }
+
+    #[test]
+    fn check_accepts_present_tools_without_version_arguments() {
+        let executor = RecordingExecutor::default().with_output(false, "usage: tmux [-V]");
+
+        check("executables: [{name: tmux}]", &executor, &Context::empty()).unwrap();
+
+        let executor = RecordingExecutor::default().with_output(true, "tmux 3.4");
+        check(
+            "executables: [{name: tmux, min_version: '3.2', version_arguments: ['-V']}]",
+            &executor,
+            &Context::empty(),
+        )
+        .unwrap();
+        assert_eq!(executor.commands(), ["tmux -V"]);
+    }
+
+    #[test]
+    fn check_compares_versions() {
+        let action = "executables: [{name: node, min_version: '18.2'}]";
+
+        let executor = RecordingExecutor::default().with_output(true, "v18.10.0");
+        check(action, &executor, &Context::empty()).unwrap();
+
+        let executor = RecordingExecutor::default().with_output(true, "v18.1.9");
+        assert!(check(action, &executor, &Context::empty()).is_err());
+
+        let executor = RecordingExecutor::default().failing_on("node --version");
+        let error = check(action, &executor, &Context::empty()).unwrap_err();
+        assert!(error.to_string().contains("node is not installed"));
+    }
+}
@@ -10,6 +10,20 @@ use crate::context::Context;
 static VARIABLE_INTERPOLATION_RGX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$\{(.*?)\}").unwrap());
 static WINDOWS_ENVIRON_RGX: Lazy<Regex> = Lazy::new(|| Regex::new(r"%(.*?)%").unwrap());
 
+/// Fill the `${name}` placeholders of an argument template in a single pass,
+/// so the inserted values are never resolved again. Unknown placeholders are kept as is.
+pub(crate) fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    VARIABLE_INTERPOLATION_RGX
+        .replace_all(template, |caps: &Captures| {
+            let name = caps.get(1).unwrap().as_str();
+            values
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map_or_else(|| caps[0].to_string(), |(_, value)| value.to_string())
+        })
+        .to_string()
+}
+
 pub trait Resolvable {
     fn resolve(&mut self, ctx: &Context);
 
@@ -34,8 +48,12 @@ impl Resolvable for String {
             let variable_name = caps.get(1).unwrap().as_str();
             if let Some(value) = ctx.get(variable_name) {
                 value.clone()
+            } else if let Some(values) = ctx.get_list(variable_name) {
+                values.join(", ")
             } else if let Ok(value) = std::env::var(variable_name) {
                 value.clone()
+            } else if ctx.keep_missing {
+                caps.get(0).unwrap().as_str().into()
             } else {
                 warn!(
                     message = "Missing variable substitution",
@@ -105,3 +123,38 @@ impl Resolvable for Url {
         *self = Url::parse(&value).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_placeholders_does_not_resolve_inserted_values() {
+        assert_eq!(
+            fill_placeholders(
+                "--command=${command}",
+                &[("command", "date +%Y-%m-%d ${HOME}")]
+            ),
+            "--command=date +%Y-%m-%d ${HOME}"
+        );
+    }
+
+    #[test]
+    fn fill_placeholders_keeps_unknown_placeholders() {
+        assert_eq!(
+            fill_placeholders("${path}:${line}", &[("path", "a.rs")]),
+            "a.rs:${line}"
+        );
+    }
+
+    #[test]
+    fn resolve_keeps_missing_variables_for_display() {
+        let ctx = Context::new([("name".to_string(), "qx".to_string())].into());
+
+        assert_eq!(
+            "${name} ${QX_UNKNOWN_VARIABLE}".resolved(&ctx.for_display()),
+            "qx ${QX_UNKNOWN_VARIABLE}"
+        );
+        assert_eq!("${name} ${QX_UNKNOWN_VARIABLE}".resolved(&ctx), "qx ");
+    }
+}
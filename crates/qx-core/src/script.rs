@@ -0,0 +1,396 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use color_eyre::{eyre::eyre, Result};
+use rhai::{module_resolvers::DummyModuleResolver, Array, Dynamic, Engine, EvalAltResult};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    context::Context,
+    intent::{CommandCreationType, CommandIntent},
+    message::MessageLevel,
+    Console, FileAccess, System,
+};
+
+/// Maximum number of operations a script can run, to stop runaway loops.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionScript {
+    pub code: Option<String>,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Default)]
+struct ScriptState {
+    context: Context,
+    intents: Vec<CommandIntent>,
+    output: Vec<String>,
+}
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+/// Run a Rhai script, returning the updated context and the commands it emitted.
+///
+/// Scripts cannot import modules or start processes by themselves, and only
+/// see the file system through `path_exists` and `is_dir`: commands are only
+/// collected, to be run by the caller through its executor. Lines written with
+/// `print` and `debug` are shown on the console once the script ends.
+pub(crate) fn run_script<F: FileAccess, C: Console>(
+    code: &str,
+    system: &System,
+    file_access: &F,
+    console: &C,
+    context: &Context,
+    environment: &HashMap<String, String>,
+) -> Result<(Context, Vec<CommandIntent>)> {
+    let state = Rc::new(RefCell::new(ScriptState {
+        context: context.clone(),
+        ..Default::default()
+    }));
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_module_resolver(DummyModuleResolver::new());
+
+    let s = state.clone();
+    engine.on_print(move |text| s.borrow_mut().output.push(text.to_string()));
+    let s = state.clone();
+    engine.on_debug(move |text, _, _| s.borrow_mut().output.push(text.to_string()));
+
+    register_variables(&mut engine, &state);
+    register_system(&mut engine, file_access);
+    register_commands(&mut engine, &state, system, environment);
+
+    let result = engine.run(code);
+
+    let state = state.take();
+    for line in &state.output {
+        console.message(&MessageLevel::Info, line, false)?;
+    }
+
+    result.map_err(|e| eyre!("Script error: {e}"))?;
+    Ok((state.context, state.intents))
+}
+
+fn register_variables(engine: &mut Engine, state: &Rc<RefCell<ScriptState>>) {
+    let s = state.clone();
+    engine.register_fn("get_var", move |name: &str| -> Dynamic {
+        let state = s.borrow();
+        if let Some(values) = state.context.get_list(name) {
+            return values.clone().into();
+        }
+
+        state
+            .context
+            .get(name)
+            .map(|v| v.clone().into())
+            .unwrap_or(Dynamic::UNIT)
+    });
+
+    let s = state.clone();
+    engine.register_fn("has_var", move |name: &str| {
+        let state = s.borrow();
+        state.context.get(name).is_some() || state.context.get_list(name).is_some()
+    });
+
+    let s = state.clone();
+    engine.register_fn("set_var", move |name: &str, value: Dynamic| {
+        let mut state = s.borrow_mut();
+        if value.is_array() {
+            let values = value.cast::<Array>();
+            state.context.set_list(
+                name.to_string(),
+                values.into_iter().map(|v| v.to_string()).collect(),
+            );
+        } else {
+            state.context.set(name.to_string(), value.to_string());
+        }
+    });
+}
+
+fn register_system<F: FileAccess>(engine: &mut Engine, file_access: &F) {
+    engine.register_fn("os", || std::env::consts::OS);
+    engine.register_fn("arch", || std::env::consts::ARCH);
+    engine.register_fn("env", |name: &str| -> Dynamic {
+        std::env::var(name).map(Into::into).unwrap_or(Dynamic::UNIT)
+    });
+    engine.register_fn("home_dir", || -> Dynamic {
+        dirs::home_dir()
+            .map(|p| p.to_string_lossy().to_string().into())
+            .unwrap_or(Dynamic::UNIT)
+    });
+    engine.register_fn("join_path", |base: &str, child: &str| {
+        Path::new(base).join(child).to_string_lossy().to_string()
+    });
+
+    let f = file_access.clone();
+    engine.register_fn("path_exists", move |path: &str| {
+        f.file_exists(Path::new(path))
+    });
+
+    let f = file_access.clone();
+    engine.register_fn("is_dir", move |path: &str| f.is_dir(Path::new(path)));
+}
+
+fn register_commands(
+    engine: &mut Engine,
+    state: &Rc<RefCell<ScriptState>>,
+    system: &System,
+    environment: &HashMap<String, String>,
+) {
+    let system = Rc::new(system.clone());
+
+    let (s, sys) = (state.clone(), system.clone());
+    engine.register_fn("open_url", move |url: &str| -> ScriptResult<()> {
+        emit_open_url(&s, &sys, url, None)
+    });
+
+    let (s, sys) = (state.clone(), system.clone());
+    engine.register_fn(
+        "open_url",
+        move |url: &str, browser: &str| -> ScriptResult<()> {
+            emit_open_url(&s, &sys, url, Some(browser))
+        },
+    );
+
+    let (s, sys) = (state.clone(), system.clone());
+    engine.register_fn("open_file", move |path: &str| {
+        let intent = sys.open_file(Path::new(path), None);
+        s.borrow_mut().intents.push(intent);
+    });
+
+    let (s, sys) = (state.clone(), system.clone());
+    engine.register_fn("open_file", move |path: &str, application: &str| {
+        let intent = sys.open_file(Path::new(path), Some(application));
+        s.borrow_mut().intents.push(intent);
+    });
+
+    let (s, sys) = (state.clone(), system.clone());
+    engine.register_fn("notify", move |title: &str, body: &str| {
+        let intent = sys.send_notification(title, body, None, None);
+        s.borrow_mut().intents.push(intent);
+    });
+
+    let (s, sys) = (state.clone(), system);
+    engine.register_fn("copy_to_clipboard", move |content: &str| {
+        let intent = sys.copy_to_clipboard(content);
+        s.borrow_mut().intents.push(intent);
+    });
+
+    let (s, env) = (state.clone(), environment.clone());
+    engine.register_fn("run", move |target: &str| {
+        emit_run(&s, &env, target, Array::new(), None)
+    });
+
+    let (s, env) = (state.clone(), environment.clone());
+    engine.register_fn("run", move |target: &str, args: Array| {
+        emit_run(&s, &env, target, args, None)
+    });
+
+    let (s, env) = (state.clone(), environment.clone());
+    engine.register_fn(
+        "run",
+        move |target: &str, args: Array, working_directory: &str| {
+            emit_run(&s, &env, target, args, Some(working_directory))
+        },
+    );
+}
+
+fn emit_open_url(
+    state: &Rc<RefCell<ScriptState>>,
+    system: &System,
+    url: &str,
+    browser: Option<&str>,
+) -> ScriptResult<()> {
+    let url = Url::parse(url).map_err(|e| format!("Invalid URL '{url}': {e}"))?;
+    let intents = system
+        .open_web_browser(&[url], browser)
+        .map_err(|e| e.to_string())?;
+
+    state.borrow_mut().intents.extend(intents);
+    Ok(())
+}
+
+fn emit_run(
+    state: &Rc<RefCell<ScriptState>>,
+    environment: &HashMap<String, String>,
+    target: &str,
+    args: Array,
+    working_directory: Option<&str>,
+) {
+    state.borrow_mut().intents.push(CommandIntent::Custom {
+        target: target.into(),
+        arguments: args.into_iter().map(|a| a.to_string()).collect(),
+        working_directory: working_directory.map(Into::into),
+        input: None,
+        environment: environment.clone(),
+        creation_type: CommandCreationType::Detach,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::Platform;
+    use crate::testing::{FakeClock, FakeConsole, MemoryFileAccess, RecordingExecutor};
+    use crate::{Action, ActionContext};
+
+    /// Run a script action, returning the context it left.
+    fn run(
+        code: &str,
+        context: Context,
+        executor: &RecordingExecutor,
+        console: &FakeConsole,
+    ) -> Result<Context> {
+        let action = Action::Script(ActionScript {
+            code: Some(code.into()),
+            path: None,
+        });
+        let mut system = System::default();
+        system.platform = Platform::Linux { wayland: false };
+        let file_access = MemoryFileAccess::default();
+        let clock = FakeClock::default();
+        let mut ctx = ActionContext::new(&system, context, executor, &file_access, console, &clock);
+
+        action.execute(&mut ctx)?;
+        Ok(ctx.context)
+    }
+
+    #[test]
+    fn variables_round_trip_into_the_context() {
+        let mut context = Context::new([("name".to_string(), "qx".to_string())].into());
+        context.set_list("hosts".into(), vec!["a".into(), "b".into()]);
+
+        let context = run(
+            r#"
+            set_var("greeting", "hello " + get_var("name"));
+            let hosts = get_var("hosts");
+            hosts.push("c");
+            set_var("hosts", hosts);
+            set_var("port", 8080);
+            set_var("checks", [has_var("name"), has_var("hosts"), has_var("missing")]);
+            "#,
+            context,
+            &RecordingExecutor::default(),
+            &FakeConsole::default(),
+        )
+        .unwrap();
+
+        assert_eq!(context.get("greeting").unwrap(), "hello qx");
+        assert_eq!(context.get("port").unwrap(), "8080");
+        assert_eq!(context.get_list("hosts").unwrap(), &["a", "b", "c"]);
+        assert_eq!(
+            context.get_list("checks").unwrap(),
+            &["true", "true", "false"]
+        );
+    }
+
+    #[test]
+    fn commands_are_sent_to_the_executor() {
+        let executor = RecordingExecutor::default();
+
+        run(
+            r#"
+            run("make", ["build", 2], "/code");
+            open_url("https://example.com/docs");
+            notify("Build", "done");
+            "#,
+            Context::empty(),
+            &executor,
+            &FakeConsole::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            executor.commands(),
+            [
+                "make build 2",
+                "open https://example.com/docs",
+                "notify-send --app-name=qx Build done",
+            ]
+        );
+        let intents = executor.intents.borrow();
+        let CommandIntent::Custom {
+            working_directory, ..
+        } = &intents[0]
+        else {
+            panic!("Unexpected intent {:?}", intents[0]);
+        };
+        assert_eq!(working_directory.as_deref(), Some(Path::new("/code")));
+    }
+
+    #[test]
+    fn failing_scripts_do_not_run_any_command() {
+        let executor = RecordingExecutor::default();
+
+        let result = run(
+            r#"run("make"); throw "broken";"#,
+            Context::empty(),
+            &executor,
+            &FakeConsole::default(),
+        );
+
+        assert!(result.is_err());
+        assert!(executor.commands().is_empty());
+    }
+
+    #[test]
+    fn infinite_loops_are_aborted() {
+        let error = run(
+            "loop {}",
+            Context::empty(),
+            &RecordingExecutor::default(),
+            &FakeConsole::default(),
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("Too many operations"), "{error}");
+    }
+
+    #[test]
+    fn modules_cannot_be_imported() {
+        // A real file, which the default resolver of the engine would load.
+        let module = std::env::temp_dir().join(format!("qx-script-{}.rhai", std::process::id()));
+        std::fs::write(&module, "export const value = 42;").unwrap();
+
+        let result = run(
+            &format!(
+                "import {:?} as lib; set_var(\"value\", lib::value);",
+                module.with_extension("")
+            ),
+            Context::empty(),
+            &RecordingExecutor::default(),
+            &FakeConsole::default(),
+        );
+        std::fs::remove_file(&module).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn printed_lines_go_to_the_console() {
+        let console = FakeConsole::default();
+
+        let result = run(
+            r#"print("step 1"); debug("step 2"); throw "broken";"#,
+            Context::empty(),
+            &RecordingExecutor::default(),
+            &console,
+        );
+
+        assert!(result.is_err());
+        assert_eq!(
+            *console.messages.borrow(),
+            [
+                (MessageLevel::Info, "step 1".to_string()),
+                (MessageLevel::Info, "\"step 2\"".to_string()),
+            ]
+        );
+    }
+}
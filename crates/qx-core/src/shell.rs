@@ -0,0 +1,263 @@
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::FileAccess;
+
+static VARIABLE_NAME_RGX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
+
+/// Shell name, set by the shell wrapper.
+const SHELL_VARIABLE: &str = "QX_SHELL";
+/// File where qx writes the commands to apply, set by the shell wrapper.
+const SHELL_FILE_VARIABLE: &str = "QX_SHELL_FILE";
+
+#[derive(Debug, Clone, Copy)]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+    Pwsh,
+}
+
+/// Changes to apply to the calling shell once an environment is booted.
+#[derive(Debug, Clone, Default)]
+pub struct ShellChanges {
+    pub working_directory: Option<PathBuf>,
+    pub environment: BTreeMap<String, String>,
+}
+
+impl ShellChanges {
+    pub fn is_empty(&self) -> bool {
+        self.working_directory.is_none() && self.environment.is_empty()
+    }
+}
+
+impl FromStr for ShellKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "pwsh" | "powershell" => Ok(Self::Pwsh),
+            _ => Err(format!(
+                "unsupported shell '{s}', expected bash, zsh, fish or pwsh"
+            )),
+        }
+    }
+}
+
+impl ShellKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Pwsh => "pwsh",
+        }
+    }
+
+    /// Shell function wrapping qx, applying the shell changes after each run.
+    pub fn init_script(&self) -> String {
+        match self {
+            Self::Bash | Self::Zsh => format!(
+                r#"qx() {{
+    local qx_shell_file qx_status
+    qx_shell_file="$(mktemp)"
+    {SHELL_VARIABLE}={name} {SHELL_FILE_VARIABLE}="$qx_shell_file" command qx "$@"
+    qx_status=$?
+    if [ -s "$qx_shell_file" ]; then
+        . "$qx_shell_file"
+    fi
+    rm -f "$qx_shell_file"
+    return $qx_status
+}}
+"#,
+                name = self.name()
+            ),
+            Self::Fish => format!(
+                r#"function qx
+    set -l qx_shell_file (mktemp)
+    {SHELL_VARIABLE}=fish {SHELL_FILE_VARIABLE}=$qx_shell_file command qx $argv
+    set -l qx_status $status
+    if test -s $qx_shell_file
+        source $qx_shell_file
+    end
+    rm -f $qx_shell_file
+    return $qx_status
+end
+"#
+            ),
+            Self::Pwsh => format!(
+                r#"function qx {{
+    $qxShellFile = New-TemporaryFile
+    $env:{SHELL_VARIABLE} = "pwsh"
+    $env:{SHELL_FILE_VARIABLE} = $qxShellFile.FullName
+    try {{
+        & (Get-Command qx -CommandType Application | Select-Object -First 1) @args
+    }} finally {{
+        Remove-Item Env:{SHELL_VARIABLE}, Env:{SHELL_FILE_VARIABLE} -ErrorAction SilentlyContinue
+    }}
+    $qxStatus = $LASTEXITCODE
+    $qxCommands = Get-Content -Raw $qxShellFile.FullName
+    Remove-Item $qxShellFile.FullName
+    if ($qxCommands) {{
+        Invoke-Expression $qxCommands
+    }}
+    $global:LASTEXITCODE = $qxStatus
+}}
+"#
+            ),
+        }
+    }
+
+    /// Shell commands applying the changes.
+    pub fn render(&self, changes: &ShellChanges) -> String {
+        let mut output = String::new();
+
+        for (name, value) in &changes.environment {
+            let value = self.quote(value);
+            output.push_str(&match self {
+                Self::Bash | Self::Zsh => format!("export {name}={value}\n"),
+                Self::Fish => format!("set -gx {name} {value}\n"),
+                Self::Pwsh => format!("$env:{name} = {value}\n"),
+            });
+        }
+
+        if let Some(directory) = &changes.working_directory {
+            let directory = self.quote(&directory.to_string_lossy());
+            output.push_str(&match self {
+                Self::Bash | Self::Zsh => format!("cd -- {directory}\n"),
+                Self::Fish => format!("cd {directory}\n"),
+                Self::Pwsh => format!("Set-Location -LiteralPath {directory}\n"),
+            });
+        }
+
+        output
+    }
+
+    fn quote(&self, value: &str) -> String {
+        match self {
+            Self::Bash | Self::Zsh => format!("'{}'", value.replace('\'', r"'\''")),
+            Self::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+            Self::Pwsh => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+/// Check that a name can be exported by every supported shell.
+pub(crate) fn check_variable_name(name: &str) -> Result<()> {
+    if !VARIABLE_NAME_RGX.is_match(name) {
+        bail!("Cannot export '{name}', variable names must match [A-Za-z_][A-Za-z0-9_]*");
+    }
+
+    Ok(())
+}
+
+/// The shell wrapper qx was started from, and the file where it expects the shell changes.
+#[derive(Debug, Clone)]
+pub struct ShellTarget {
+    shell: ShellKind,
+    path: PathBuf,
+}
+
+impl ShellTarget {
+    /// Read the variables set by the shell wrapper, and remove them from the
+    /// environment so they do not leak into the processes started by qx.
+    ///
+    /// Returns `None` when qx was not started by the shell wrapper.
+    pub fn take_from_env() -> Result<Option<Self>> {
+        let shell = std::env::var(SHELL_VARIABLE);
+        let path = std::env::var_os(SHELL_FILE_VARIABLE);
+        std::env::remove_var(SHELL_VARIABLE);
+        std::env::remove_var(SHELL_FILE_VARIABLE);
+
+        let (Ok(shell), Some(path)) = (shell, path) else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            shell: ShellKind::from_str(&shell).map_err(|e| eyre!(e))?,
+            path: path.into(),
+        }))
+    }
+
+    /// Write the shell changes for the shell wrapper.
+    pub fn write<F: FileAccess>(&self, file_access: &F, changes: &ShellChanges) -> Result<()> {
+        for name in changes.environment.keys() {
+            check_variable_name(name)?;
+        }
+
+        file_access.write(&self.path, self.shell.render(changes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes() -> ShellChanges {
+        ShellChanges {
+            working_directory: Some("/code/it's here".into()),
+            environment: BTreeMap::from([
+                ("GREETING".to_string(), r"it's a \ test".to_string()),
+                ("RUST_LOG".to_string(), "debug".to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn render_bash_and_zsh() {
+        let expected = r#"export GREETING='it'\''s a \ test'
+export RUST_LOG='debug'
+cd -- '/code/it'\''s here'
+"#;
+
+        assert_eq!(ShellKind::Bash.render(&changes()), expected);
+        assert_eq!(ShellKind::Zsh.render(&changes()), expected);
+    }
+
+    #[test]
+    fn render_fish() {
+        assert_eq!(
+            ShellKind::Fish.render(&changes()),
+            r#"set -gx GREETING 'it\'s a \\ test'
+set -gx RUST_LOG 'debug'
+cd '/code/it\'s here'
+"#
+        );
+    }
+
+    #[test]
+    fn render_pwsh() {
+        assert_eq!(
+            ShellKind::Pwsh.render(&changes()),
+            r#"$env:GREETING = 'it''s a \ test'
+$env:RUST_LOG = 'debug'
+Set-Location -LiteralPath '/code/it''s here'
+"#
+        );
+    }
+
+    #[test]
+    fn quote_keeps_expansions_literal() {
+        for shell in [ShellKind::Bash, ShellKind::Fish, ShellKind::Pwsh] {
+            assert_eq!(shell.quote("$HOME $(id) `id`"), "'$HOME $(id) `id`'");
+        }
+    }
+
+    #[test]
+    fn check_variable_name_rejects_invalid_names() {
+        assert!(check_variable_name("_PATH2").is_ok());
+        for name in ["", "2PATH", "MY-VAR", "A B", "X;rm -rf /", "$(id)"] {
+            assert!(check_variable_name(name).is_err(), "{name}");
+        }
+    }
+}
@@ -1,21 +1,84 @@
+use color_eyre::{eyre::eyre, Result};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use url::Url;
 
 use crate::{
+    browser::BrowserProfile,
+    editor::{EditorProfile, EditorTarget},
+    filesystem::{normalize_path, resolve_path},
     intent::{CommandCreationType, CommandIntent},
     resolvable::ResolvableClone,
+    terminal::TerminalProfile,
+    vscode::{VSCodeRemote, VSCodeWindow},
+    FileAccess,
 };
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationUrgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl NotificationUrgency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// Operating system family, deciding the default notification and clipboard commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Platform {
+    Windows,
+    MacOs,
+    Linux { wayland: bool },
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        if cfg!(windows) {
+            Self::Windows
+        } else if cfg!(target_os = "macos") {
+            Self::MacOs
+        } else {
+            Self::Linux {
+                wayland: std::env::var_os("WAYLAND_DISPLAY").is_some(),
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct System {
     web_browser_path: Option<PathBuf>,
     web_browser_arguments: Option<Vec<String>>,
+    browsers: Option<HashMap<String, BrowserProfile>>,
     editor_path: Option<PathBuf>,
     editor_arguments: Option<Vec<String>>,
     vscode_path: Option<PathBuf>,
+    notification_path: Option<PathBuf>,
+    clipboard_path: Option<PathBuf>,
+    clipboard_arguments: Option<Vec<String>>,
+    removable_paths: Option<Vec<PathBuf>>,
+    git_path: Option<PathBuf>,
+    terminal: Option<TerminalProfile>,
+    tmux_path: Option<PathBuf>,
+    shell_path: Option<PathBuf>,
+    editors: Option<HashMap<String, EditorProfile>>,
     #[serde(default)]
     defaults_to_interactive: bool,
+    #[serde(skip)]
+    pub(crate) platform: Platform,
 }
 
 impl System {
@@ -32,48 +95,277 @@ impl System {
         }
     }
 
+    fn get_notification_executable(&self) -> PathBuf {
+        if let Some(value) = self.notification_path.as_ref() {
+            return value.into();
+        }
+
+        match self.platform {
+            Platform::Windows => "powershell".into(),
+            Platform::MacOs => "osascript".into(),
+            Platform::Linux { .. } => "notify-send".into(),
+        }
+    }
+
+    fn get_clipboard_command(&self) -> (PathBuf, Vec<String>) {
+        if let Some(value) = self.clipboard_path.as_ref() {
+            return (
+                value.into(),
+                self.clipboard_arguments.clone().unwrap_or_default(),
+            );
+        }
+
+        match self.platform {
+            Platform::Windows => ("clip.exe".into(), vec![]),
+            Platform::MacOs => ("pbcopy".into(), vec![]),
+            Platform::Linux { wayland: true } => ("wl-copy".into(), vec![]),
+            Platform::Linux { wayland: false } => (
+                "xclip".into(),
+                vec!["-selection".into(), "clipboard".into()],
+            ),
+        }
+    }
+
+    fn get_git_executable(&self) -> PathBuf {
+        if let Some(value) = self.git_path.as_ref() {
+            value.into()
+        } else {
+            "git".into()
+        }
+    }
+
+    fn get_tmux_executable(&self) -> PathBuf {
+        if let Some(value) = self.tmux_path.as_ref() {
+            value.into()
+        } else {
+            "tmux".into()
+        }
+    }
+
+    fn get_shell_executable(&self) -> PathBuf {
+        if let Some(value) = self.shell_path.as_ref() {
+            value.into()
+        } else if cfg!(windows) {
+            std::env::var_os("COMSPEC")
+                .map(Into::into)
+                .unwrap_or_else(|| "cmd.exe".into())
+        } else {
+            std::env::var_os("SHELL")
+                .map(Into::into)
+                .unwrap_or_else(|| "/bin/sh".into())
+        }
+    }
+
+    fn get_editor_profile(&self, name: &str) -> Result<EditorProfile> {
+        if let Some(profile) = self.editors.as_ref().and_then(|e| e.get(name)) {
+            return Ok(profile.clone());
+        }
+
+        let mut presets = EditorProfile::presets(self.get_vscode_executable());
+        presets.remove(name).ok_or_else(|| {
+            let known_names = presets
+                .keys()
+                .chain(self.editors.iter().flat_map(|e| e.keys()))
+                .sorted()
+                .join(", ");
+
+            eyre!("Unknown editor '{name}', known editors are: {known_names}")
+        })
+    }
+
+    fn get_terminal_profile(&self) -> TerminalProfile {
+        self.terminal.clone().unwrap_or_default()
+    }
+
     pub fn should_defaults_to_interactive(&self) -> bool {
         self.defaults_to_interactive
     }
 
-    pub fn open_web_browser(&self, target: &Url) -> CommandIntent {
-        if let Some(value) = self.web_browser_path.as_ref() {
-            CommandIntent::Custom {
+    /// Check a path against the `removable_paths` allowlist, after resolving
+    /// symlinks, so a link cannot point the removal outside of the allowlist.
+    pub fn is_removable_path<F: FileAccess>(&self, file_access: &F, target: &Path) -> bool {
+        // The last component is kept as is: removing a symlink only removes the link.
+        let target = normalize_path(target);
+        let target = match (target.parent(), target.file_name()) {
+            (Some(parent), Some(name)) => resolve_path(file_access, parent).join(name),
+            _ => target,
+        };
+
+        self.removable_paths.iter().flatten().any(|allowed| {
+            let allowed = resolve_path(file_access, &allowed.resolved_without_context());
+            target != allowed && target.starts_with(allowed)
+        })
+    }
+
+    pub fn open_web_browser(
+        &self,
+        targets: &[Url],
+        browser: Option<&str>,
+    ) -> Result<Vec<CommandIntent>> {
+        let targets: Vec<_> = targets.iter().map(|t| t.to_string()).collect();
+
+        if let Some(name) = browser {
+            let profile = self
+                .browsers
+                .as_ref()
+                .and_then(|b| b.get(name))
+                .ok_or_else(|| {
+                    let known_names = self
+                        .browsers
+                        .iter()
+                        .flat_map(|b| b.keys())
+                        .sorted()
+                        .join(", ");
+                    eyre!("Unknown browser '{name}', known browsers are: {known_names}")
+                })?;
+
+            Ok(vec![CommandIntent::Custom {
+                target: profile.path.clone(),
+                arguments: profile.build_arguments(&targets),
+                working_directory: None,
+                input: None,
+                environment: HashMap::new(),
+                creation_type: CommandCreationType::Detach,
+            }])
+        } else if let Some(value) = self.web_browser_path.as_ref() {
+            Ok(vec![CommandIntent::Custom {
                 target: value.into(),
                 arguments: self
                     .web_browser_arguments
                     .iter()
                     .flatten()
                     .cloned()
-                    .chain(std::iter::once(target.to_string()))
+                    .chain(targets)
                     .collect(),
                 working_directory: None,
+                input: None,
+                environment: HashMap::new(),
                 creation_type: CommandCreationType::Detach,
-            }
+            }])
         } else {
-            CommandIntent::System {
-                target: target.to_string().into(),
-                creation_type: CommandCreationType::Detach,
-            }
+            // The default browser can only open targets one by one.
+            Ok(targets
+                .into_iter()
+                .map(|target| CommandIntent::System {
+                    target: target.into(),
+                    application: None,
+                    creation_type: CommandCreationType::Detach,
+                })
+                .collect())
         }
     }
 
-    pub fn open_vscode(&self, target: &Path) -> CommandIntent {
+    /// Open targets in VS Code, using the `vscode` editor profile so its
+    /// overridden arguments apply.
+    pub fn open_vscode<F: FileAccess>(
+        &self,
+        file_access: &F,
+        targets: &[PathBuf],
+        window: Option<&VSCodeWindow>,
+        profile: Option<&str>,
+        remote: Option<&VSCodeRemote>,
+    ) -> Result<CommandIntent> {
+        let editor = self.get_editor_profile("vscode")?;
+
         let mut arguments = vec![];
-        if target.starts_with("vscode-remote://") {
-            arguments.push("--folder-uri".into());
+        if let Some(window) = window {
+            arguments.push(window.as_argument().into());
         }
 
-        arguments.push(target.to_string_lossy().to_string());
+        if let Some(profile) = profile {
+            arguments.extend(["--profile".into(), profile.into()]);
+        }
 
-        CommandIntent::Custom {
-            target: self.get_vscode_executable(),
+        for target in targets {
+            let target_str = target.to_string_lossy();
+            let uri = remote.map(|r| r.build_uri(target));
+            let editor_target = match &uri {
+                Some(uri) => EditorTarget::Remote(uri),
+                None => Self::editor_target(&target_str, None, file_access.is_dir(target)),
+            };
+
+            let mut target_arguments = editor
+                .build_arguments(&editor_target)
+                .ok_or_else(|| eyre!("Editor 'vscode' does not support remote targets"))?;
+
+            // Remote workspaces are files, not folders.
+            let is_workspace = target
+                .extension()
+                .is_some_and(|ext| ext == "code-workspace");
+            if is_workspace && matches!(editor_target, EditorTarget::Remote(_)) {
+                for argument in &mut target_arguments {
+                    if argument == "--folder-uri" {
+                        *argument = "--file-uri".into();
+                    }
+                }
+            }
+
+            arguments.extend(target_arguments);
+        }
+
+        Ok(CommandIntent::Custom {
+            target: editor.path,
             arguments,
             working_directory: None,
+            input: None,
+            environment: HashMap::new(),
             creation_type: CommandCreationType::Detach,
+        })
+    }
+
+    /// Classify a target: URIs like `ssh://host/path` are remote, the rest are local paths.
+    fn editor_target(target: &str, line: Option<u32>, is_dir: bool) -> EditorTarget<'_> {
+        if is_remote_uri(target) {
+            EditorTarget::Remote(target)
+        } else if let Some(line) = line {
+            EditorTarget::FileAtLine(target, line)
+        } else if is_dir {
+            EditorTarget::Folder(target)
+        } else {
+            EditorTarget::File(target)
         }
     }
 
+    pub fn open_in_editor(
+        &self,
+        editor: &str,
+        target: &Path,
+        line: Option<u32>,
+        is_dir: bool,
+    ) -> Result<CommandIntent> {
+        let profile = self.get_editor_profile(editor)?;
+        let target_str = target.to_string_lossy();
+        let editor_target = Self::editor_target(&target_str, line, is_dir);
+
+        let arguments = profile
+            .build_arguments(&editor_target)
+            .ok_or_else(|| eyre!("Editor '{editor}' does not support remote targets"))?;
+
+        if profile.terminal {
+            let working_directory = match editor_target {
+                EditorTarget::Folder(_) => Some(target),
+                _ => target.parent(),
+            }
+            .unwrap_or(Path::new("."));
+
+            let command_line = std::iter::once(profile.path.to_string_lossy().to_string())
+                .chain(arguments)
+                .map(|a| quote_argument(&a))
+                .join(" ");
+
+            return self.open_terminal(working_directory, Some(editor), Some(&command_line));
+        }
+
+        Ok(CommandIntent::Custom {
+            target: profile.path,
+            arguments,
+            working_directory: None,
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Detach,
+        })
+    }
+
     pub fn open_editor(&self, target: &Path) -> CommandIntent {
         if let Some(editor_path) = self.editor_path.as_ref() {
             CommandIntent::Custom {
@@ -86,20 +378,352 @@ impl System {
                     .chain(std::iter::once(target.to_string_lossy().to_string()))
                     .collect(),
                 working_directory: None,
+                input: None,
+                environment: HashMap::new(),
                 creation_type: CommandCreationType::Wait,
             }
         } else {
             CommandIntent::System {
                 target: target.into(),
+                application: None,
                 creation_type: CommandCreationType::Wait,
             }
         }
     }
 
-    pub fn open_file(&self, target: &Path) -> CommandIntent {
+    pub fn open_file(&self, target: &Path, application: Option<&str>) -> CommandIntent {
         CommandIntent::System {
             target: target.into(),
+            application: application.map(Into::into),
             creation_type: CommandCreationType::Detach,
         }
     }
+
+    pub fn send_notification(
+        &self,
+        title: &str,
+        body: &str,
+        urgency: Option<&NotificationUrgency>,
+        icon: Option<&Path>,
+    ) -> CommandIntent {
+        let arguments = match self.platform {
+            Platform::Windows => {
+                // Use a tray balloon tip, which does not need any additional module.
+                let script = format!(
+                    "Add-Type -AssemblyName System.Windows.Forms; \
+                    $n = New-Object System.Windows.Forms.NotifyIcon; \
+                    $n.Icon = {}; \
+                    $n.BalloonTipIcon = '{}'; \
+                    $n.Visible = $true; \
+                    $n.ShowBalloonTip(10000, '{}', '{}', $n.BalloonTipIcon); \
+                    Start-Sleep -Seconds 10; \
+                    $n.Dispose()",
+                    icon.map(|i| format!(
+                        "New-Object System.Drawing.Icon('{}')",
+                        escape_powershell(&i.to_string_lossy())
+                    ))
+                    .unwrap_or_else(|| "[System.Drawing.SystemIcons]::Information".into()),
+                    match urgency {
+                        Some(NotificationUrgency::Critical) => "Error",
+                        Some(NotificationUrgency::Low) => "None",
+                        _ => "Info",
+                    },
+                    escape_powershell(title),
+                    escape_powershell(body)
+                );
+
+                vec![
+                    "-NoProfile".into(),
+                    "-WindowStyle".into(),
+                    "Hidden".into(),
+                    "-Command".into(),
+                    script,
+                ]
+            }
+            Platform::MacOs => {
+                // Urgency and icons are not supported by AppleScript notifications.
+                vec![
+                    "-e".into(),
+                    format!(
+                        "display notification \"{}\" with title \"{}\"",
+                        escape_applescript(body),
+                        escape_applescript(title)
+                    ),
+                ]
+            }
+            Platform::Linux { .. } => {
+                let mut arguments = vec![];
+                if let Some(urgency) = urgency {
+                    arguments.push(format!("--urgency={}", urgency.as_str()));
+                }
+                if let Some(icon) = icon {
+                    arguments.push(format!("--icon={}", icon.to_string_lossy()));
+                }
+                arguments.push("--app-name=qx".into());
+                arguments.push(title.into());
+                arguments.push(body.into());
+                arguments
+            }
+        };
+
+        CommandIntent::Custom {
+            target: self.get_notification_executable(),
+            arguments,
+            working_directory: None,
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Detach,
+        }
+    }
+
+    pub fn copy_to_clipboard(&self, content: &str) -> CommandIntent {
+        let (target, arguments) = self.get_clipboard_command();
+
+        CommandIntent::Custom {
+            target,
+            arguments,
+            working_directory: None,
+            input: Some(content.into()),
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Wait,
+        }
+    }
+
+    pub fn open_terminal(
+        &self,
+        working_directory: &Path,
+        title: Option<&str>,
+        command: Option<&str>,
+    ) -> Result<CommandIntent> {
+        let (target, arguments) = self.get_terminal_profile().build_command(
+            &working_directory.to_string_lossy(),
+            title,
+            command,
+        )?;
+
+        Ok(CommandIntent::Custom {
+            target,
+            arguments,
+            working_directory: Some(working_directory.into()),
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Detach,
+        })
+    }
+
+    pub fn run_git(
+        &self,
+        arguments: Vec<String>,
+        working_directory: Option<&Path>,
+    ) -> CommandIntent {
+        CommandIntent::Custom {
+            target: self.get_git_executable(),
+            arguments,
+            working_directory: working_directory.map(Into::into),
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Wait,
+        }
+    }
+
+    pub fn list_processes(&self) -> CommandIntent {
+        let (target, arguments) = if cfg!(windows) {
+            (
+                "powershell".into(),
+                vec![
+                    "-NoProfile".into(),
+                    "-Command".into(),
+                    "Get-CimInstance Win32_Process | ForEach-Object { \
+                    \"$($_.ProcessId)`t$($_.Name)`t$($_.CommandLine)\" }"
+                        .into(),
+                ],
+            )
+        } else {
+            (
+                "ps".into(),
+                vec![
+                    "-A".into(),
+                    "-ww".into(),
+                    "-o".into(),
+                    "pid=".into(),
+                    "-o".into(),
+                    "args=".into(),
+                ],
+            )
+        };
+
+        CommandIntent::Custom {
+            target,
+            arguments,
+            working_directory: None,
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Wait,
+        }
+    }
+
+    pub fn terminate_processes(&self, pids: &[u32], force: bool) -> CommandIntent {
+        let (target, arguments) = if cfg!(windows) {
+            let mut arguments = vec![];
+            if force {
+                arguments.push("/F".into());
+            }
+            for pid in pids {
+                arguments.extend(["/PID".into(), pid.to_string()]);
+            }
+
+            ("taskkill".into(), arguments)
+        } else {
+            let signal = if force { "-KILL" } else { "-TERM" };
+
+            (
+                "kill".into(),
+                std::iter::once(signal.to_string())
+                    .chain(pids.iter().map(|p| p.to_string()))
+                    .collect(),
+            )
+        };
+
+        CommandIntent::Custom {
+            target,
+            arguments,
+            working_directory: None,
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Wait,
+        }
+    }
+
+    pub fn spawn_background(&self, task: String) -> Result<CommandIntent> {
+        Ok(CommandIntent::Custom {
+            target: std::env::current_exe()?,
+            arguments: vec!["--background".into()],
+            working_directory: None,
+            input: Some(task),
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Detach,
+        })
+    }
+
+    pub fn run_tmux(&self, arguments: Vec<String>) -> CommandIntent {
+        CommandIntent::Custom {
+            target: self.get_tmux_executable(),
+            arguments,
+            working_directory: None,
+            input: None,
+            environment: HashMap::new(),
+            creation_type: CommandCreationType::Wait,
+        }
+    }
+
+    pub fn spawn_shell(
+        &self,
+        working_directory: Option<&Path>,
+        environment: HashMap<String, String>,
+    ) -> CommandIntent {
+        CommandIntent::Custom {
+            target: self.get_shell_executable(),
+            arguments: vec![],
+            working_directory: working_directory.map(Into::into),
+            input: None,
+            environment,
+            creation_type: CommandCreationType::Wait,
+        }
+    }
+}
+
+/// Check if a target is a URI with a scheme, like `ssh://host/path`.
+///
+/// Single letter schemes are Windows drive letters, so they are local paths.
+fn is_remote_uri(target: &str) -> bool {
+    let Some((scheme, _)) = target.split_once("://") else {
+        return false;
+    };
+
+    scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+fn quote_argument(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.into()
+    }
+}
+
+fn escape_powershell(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+fn escape_applescript(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MemoryFileAccess, RecordingExecutor};
+    use crate::CommandExecutor;
+
+    fn vscode_commands(system: &System, targets: &[&str], remote: Option<&VSCodeRemote>) -> String {
+        let file_access = MemoryFileAccess::default().with_dir("/code/project");
+        let targets: Vec<PathBuf> = targets.iter().map(PathBuf::from).collect();
+        let executor = RecordingExecutor::default();
+        executor
+            .execute(
+                system
+                    .open_vscode(&file_access, &targets, None, None, remote)
+                    .unwrap(),
+            )
+            .unwrap();
+
+        executor.commands().remove(0)
+    }
+
+    #[test]
+    fn open_vscode_uses_overridden_editor_arguments() {
+        let system: System = serde_yaml::from_str(
+            r#"
+            editors:
+              vscode:
+                path: code-insiders
+                folder_arguments: ["--add", "${path}"]
+                remote_arguments: ["--remote", "${uri}"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vscode_commands(&system, &["/code/project", "ssh://host/code"], None),
+            "code-insiders --add /code/project --remote ssh://host/code"
+        );
+    }
+
+    #[test]
+    fn open_vscode_opens_remote_workspaces_as_files() {
+        let remote = VSCodeRemote::Ssh { host: "box".into() };
+
+        assert_eq!(
+            vscode_commands(
+                &System::default(),
+                &["/code/app.code-workspace"],
+                Some(&remote)
+            ),
+            "code --file-uri vscode-remote://ssh-remote+box/code/app.code-workspace"
+        );
+    }
+
+    #[test]
+    fn is_remote_uri_only_matches_schemes() {
+        assert!(is_remote_uri("ssh://host/path"));
+        assert!(is_remote_uri("vscode-remote://wsl+Ubuntu/home"));
+        assert!(!is_remote_uri("/home/me/a://b"));
+        assert!(!is_remote_uri("C://Users/me"));
+        assert!(!is_remote_uri("my folder://x"));
+        assert!(!is_remote_uri("/code/project"));
+    }
 }
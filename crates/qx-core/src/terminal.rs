@@ -0,0 +1,203 @@
+use std::path::PathBuf;
+
+use color_eyre::{eyre::bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::resolvable::fill_placeholders;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalPreset {
+    Kitty,
+    Wezterm,
+    GnomeTerminal,
+    WindowsTerminal,
+    TerminalApp,
+    Custom,
+}
+
+impl Default for TerminalPreset {
+    fn default() -> Self {
+        if cfg!(windows) {
+            Self::WindowsTerminal
+        } else if cfg!(target_os = "macos") {
+            Self::TerminalApp
+        } else {
+            Self::GnomeTerminal
+        }
+    }
+}
+
+/// How to launch a terminal emulator.
+///
+/// Argument templates can use the `${working_directory}`, `${title}` and `${command}` variables.
+/// `title_arguments` and `command_arguments` are only added when a title or a command is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TerminalProfile {
+    #[serde(default)]
+    pub preset: TerminalPreset,
+    pub path: Option<PathBuf>,
+    pub arguments: Option<Vec<String>>,
+    pub title_arguments: Option<Vec<String>>,
+    pub command_arguments: Option<Vec<String>>,
+}
+
+impl TerminalProfile {
+    fn preset_executable(&self) -> Option<PathBuf> {
+        match self.preset {
+            TerminalPreset::Kitty => Some("kitty".into()),
+            TerminalPreset::Wezterm => Some("wezterm".into()),
+            TerminalPreset::GnomeTerminal => Some("gnome-terminal".into()),
+            TerminalPreset::WindowsTerminal => Some("wt.exe".into()),
+            TerminalPreset::TerminalApp => Some("open".into()),
+            TerminalPreset::Custom => None,
+        }
+    }
+
+    fn preset_arguments(&self) -> Vec<&'static str> {
+        match self.preset {
+            TerminalPreset::Kitty => vec!["--directory", "${working_directory}"],
+            TerminalPreset::Wezterm => vec!["start", "--cwd", "${working_directory}"],
+            TerminalPreset::GnomeTerminal => vec!["--working-directory=${working_directory}"],
+            TerminalPreset::WindowsTerminal => vec!["-d", "${working_directory}"],
+            TerminalPreset::TerminalApp => vec!["-a", "Terminal", "${working_directory}"],
+            TerminalPreset::Custom => vec![],
+        }
+    }
+
+    fn preset_title_arguments(&self) -> Vec<&'static str> {
+        match self.preset {
+            TerminalPreset::Kitty => vec!["--title", "${title}"],
+            // WezTerm and Terminal.app have no command-line switch for the window title.
+            TerminalPreset::Wezterm | TerminalPreset::TerminalApp => vec![],
+            TerminalPreset::GnomeTerminal => vec!["--title=${title}"],
+            TerminalPreset::WindowsTerminal => vec!["--title", "${title}"],
+            TerminalPreset::Custom => vec![],
+        }
+    }
+
+    fn preset_command_arguments(&self) -> Vec<&'static str> {
+        match self.preset {
+            TerminalPreset::Kitty => vec!["sh", "-c", "${command}"],
+            TerminalPreset::Wezterm | TerminalPreset::GnomeTerminal => {
+                vec!["--", "sh", "-c", "${command}"]
+            }
+            TerminalPreset::WindowsTerminal => vec!["cmd", "/k", "${command}"],
+            TerminalPreset::TerminalApp | TerminalPreset::Custom => vec![],
+        }
+    }
+
+    pub fn build_command(
+        &self,
+        working_directory: &str,
+        title: Option<&str>,
+        command: Option<&str>,
+    ) -> Result<(PathBuf, Vec<String>)> {
+        let Some(executable) = self.path.clone().or_else(|| self.preset_executable()) else {
+            bail!("The 'custom' terminal preset needs a 'path' to the terminal executable");
+        };
+
+        if command.is_some()
+            && self.command_arguments.is_none()
+            && matches!(self.preset, TerminalPreset::TerminalApp)
+        {
+            bail!("The 'terminal_app' terminal preset cannot run commands, choose another preset");
+        }
+
+        let values = [
+            ("working_directory", working_directory),
+            ("title", title.unwrap_or_default()),
+            ("command", command.unwrap_or_default()),
+        ];
+
+        let templates = |custom: &Option<Vec<String>>, preset: Vec<&'static str>| {
+            custom
+                .clone()
+                .unwrap_or_else(|| preset.into_iter().map(Into::into).collect())
+                .into_iter()
+                .map(|a| fill_placeholders(&a, &values))
+                .collect::<Vec<_>>()
+        };
+
+        let mut arguments = templates(&self.arguments, self.preset_arguments());
+        if title.is_some() {
+            arguments.extend(templates(
+                &self.title_arguments,
+                self.preset_title_arguments(),
+            ));
+        }
+        if command.is_some() {
+            arguments.extend(templates(
+                &self.command_arguments,
+                self.preset_command_arguments(),
+            ));
+        }
+
+        Ok((executable, arguments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(yaml: &str) -> TerminalProfile {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn build_command_uses_preset_arguments() {
+        let (executable, arguments) = profile("preset: kitty")
+            .build_command("/code", Some("logs"), Some("tail -f app.log"))
+            .unwrap();
+
+        assert_eq!(executable, PathBuf::from("kitty"));
+        assert_eq!(
+            arguments,
+            [
+                "--directory",
+                "/code",
+                "--title",
+                "logs",
+                "sh",
+                "-c",
+                "tail -f app.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn build_command_inserts_commands_literally() {
+        let (_, arguments) = profile("preset: gnome_terminal")
+            .build_command("/code", None, Some("date +%Y-%m-%d && echo %PATH% ${HOME}"))
+            .unwrap();
+
+        assert_eq!(
+            arguments.last().unwrap(),
+            "date +%Y-%m-%d && echo %PATH% ${HOME}"
+        );
+    }
+
+    #[test]
+    fn build_command_requires_a_custom_executable() {
+        assert!(profile("preset: custom")
+            .build_command("/code", None, None)
+            .is_err());
+        assert!(
+            profile("{preset: custom, path: alacritty, arguments: ['--working-directory', '${working_directory}']}")
+                .build_command("/code", None, None)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn terminal_app_cannot_run_commands() {
+        let profile = profile("preset: terminal_app");
+
+        assert_eq!(
+            profile.build_command("/code", None, None).unwrap().1,
+            ["-a", "Terminal", "/code"]
+        );
+        assert!(profile.build_command("/code", None, Some("ls")).is_err());
+    }
+}
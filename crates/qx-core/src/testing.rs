@@ -0,0 +1,543 @@
+//! Fake implementations of the I/O traits, for tests.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    ffi::OsString,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::{eyre::bail, Result};
+
+use crate::{
+    intent::{CommandIntent, CommandOutput},
+    Clock, CommandExecutor, Console, FileAccess, MessageLevel, WriteSeek,
+};
+
+/// Maximum number of symlinks followed while resolving a path, like Linux.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+#[derive(Debug, Clone)]
+enum Node {
+    File {
+        data: Vec<u8>,
+        modified: SystemTime,
+        mode: u32,
+    },
+    Dir,
+    Symlink(PathBuf),
+}
+
+#[derive(Default)]
+struct MemoryState {
+    nodes: BTreeMap<PathBuf, Node>,
+    ticks: u64,
+    unreadable: Vec<PathBuf>,
+}
+
+/// An in-memory file system, only supporting absolute paths.
+///
+/// Each write advances the modification time by one second.
+#[derive(Clone)]
+pub struct MemoryFileAccess {
+    state: Rc<RefCell<MemoryState>>,
+}
+
+impl Default for MemoryFileAccess {
+    fn default() -> Self {
+        let mut state = MemoryState::default();
+        state.nodes.insert("/".into(), Node::Dir);
+
+        Self {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+}
+
+impl MemoryFileAccess {
+    /// Create a file and its parent folders.
+    pub fn with_file(self, path: &str, data: &str) -> Self {
+        let path = Path::new(path);
+        self.create_dir_all(path.parent().unwrap()).unwrap();
+        self.write(path, data).unwrap();
+        self
+    }
+
+    pub fn with_dir(self, path: &str) -> Self {
+        self.create_dir_all(Path::new(path)).unwrap();
+        self
+    }
+
+    pub fn with_symlink(self, source: &str, target: &str) -> Self {
+        self.symlink(Path::new(source), Path::new(target)).unwrap();
+        self
+    }
+
+    /// Make reads of a file fail, while it still exists.
+    pub fn failing_on(self, path: &str) -> Self {
+        self.state.borrow_mut().unreadable.push(path.into());
+        self
+    }
+
+    /// Change the permissions of an existing file.
+    pub fn with_permissions(self, path: &str, permissions: u32) -> Self {
+        let (path, _) = self.node(Path::new(path), true).unwrap();
+        if let Some(Node::File { mode, .. }) = self.state.borrow_mut().nodes.get_mut(&path) {
+            *mode = permissions;
+        }
+        self
+    }
+
+    fn resolve(&self, path: &Path, follow_last: bool) -> Result<PathBuf> {
+        if !path.is_absolute() {
+            bail!("Only absolute paths are supported, got {:?}", path);
+        }
+
+        let state = self.state.borrow();
+        let mut remaining: Vec<OsString> = path
+            .components()
+            .map(|c| c.as_os_str().to_owned())
+            .rev()
+            .collect();
+        let mut current = PathBuf::from("/");
+        let mut hops = 0;
+
+        while let Some(part) = remaining.pop() {
+            match Path::new(&part).components().next() {
+                Some(Component::RootDir) => current = PathBuf::from("/"),
+                Some(Component::CurDir) | None => (),
+                Some(Component::ParentDir) => {
+                    current.pop();
+                }
+                _ => {
+                    let next = current.join(&part);
+                    match state.nodes.get(&next) {
+                        Some(Node::Symlink(target)) if follow_last || !remaining.is_empty() => {
+                            hops += 1;
+                            if hops > MAX_SYMLINK_HOPS {
+                                bail!("Too many levels of symbolic links in {:?}", path);
+                            }
+
+                            remaining.extend(
+                                target.components().map(|c| c.as_os_str().to_owned()).rev(),
+                            );
+                        }
+                        _ => current = next,
+                    }
+                }
+            }
+        }
+
+        Ok(current)
+    }
+
+    fn node(&self, path: &Path, follow_last: bool) -> Option<(PathBuf, Node)> {
+        let path = self.resolve(path, follow_last).ok()?;
+        let node = self.state.borrow().nodes.get(&path).cloned()?;
+        Some((path, node))
+    }
+
+    fn insert_file(&self, path: &Path, data: Vec<u8>) -> Result<()> {
+        let path = self.resolve(path, true)?;
+        if !matches!(
+            self.node(path.parent().unwrap(), true),
+            Some((_, Node::Dir))
+        ) {
+            bail!("Parent folder of {:?} does not exist", path);
+        }
+        if let Some((_, Node::Dir)) = self.node(&path, true) {
+            bail!("Path {:?} is a directory", path);
+        }
+
+        let mut state = self.state.borrow_mut();
+        state.ticks += 1;
+        let modified = UNIX_EPOCH + Duration::from_secs(state.ticks);
+        // Overwritten files keep their permissions.
+        let mode = match state.nodes.get(&path) {
+            Some(Node::File { mode, .. }) => *mode,
+            _ => 0o644,
+        };
+        state.nodes.insert(
+            path,
+            Node::File {
+                data,
+                modified,
+                mode,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn remove_tree(&self, path: &Path) {
+        self.state
+            .borrow_mut()
+            .nodes
+            .retain(|p, _| !p.starts_with(path));
+    }
+}
+
+impl FileAccess for MemoryFileAccess {
+    fn user_data_directory(&self) -> PathBuf {
+        "/data".into()
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        Ok(String::from_utf8(self.read(path)?)?)
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        if self.state.borrow().unreadable.iter().any(|p| p == path) {
+            bail!("File {:?} cannot be read", path);
+        }
+
+        match self.node(path, true) {
+            Some((_, Node::File { data, .. })) => Ok(data),
+            _ => bail!("File {:?} does not exist", path),
+        }
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(self.read(path)?)))
+    }
+
+    fn write<D: AsRef<[u8]>>(&self, path: &Path, data: D) -> Result<()> {
+        self.insert_file(path, data.as_ref().to_vec())
+    }
+
+    fn create(&self, path: &Path) -> Result<Box<dyn WriteSeek>> {
+        self.insert_file(path, vec![])?;
+
+        Ok(Box::new(MemoryFile {
+            file_access: self.clone(),
+            path: path.to_path_buf(),
+            data: Cursor::new(vec![]),
+        }))
+    }
+
+    fn create_new(&self, path: &Path) -> Result<bool> {
+        if self.node(path, false).is_some() {
+            return Ok(false);
+        }
+
+        self.insert_file(path, vec![])?;
+        Ok(true)
+    }
+
+    fn append<D: AsRef<[u8]>>(&self, path: &Path, data: D) -> Result<()> {
+        let mut content = self.read(path).unwrap_or_default();
+        content.extend_from_slice(data.as_ref());
+        self.insert_file(path, content)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let path = self.resolve(path, true)?;
+        for ancestor in path.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            match self.node(ancestor, true) {
+                Some((_, Node::Dir)) => (),
+                Some(_) => bail!("Path {:?} already exists and is not a directory", ancestor),
+                None => {
+                    self.state
+                        .borrow_mut()
+                        .nodes
+                        .insert(ancestor.to_path_buf(), Node::Dir);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn file_exists(&self, path: &Path) -> bool {
+        self.node(path, true).is_some()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.node(path, true), Some((_, Node::Dir)))
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        matches!(self.node(path, false), Some((_, Node::Symlink(_))))
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime> {
+        match self.node(path, true) {
+            Some((_, Node::File { modified, .. })) => Ok(modified),
+            Some(_) => Ok(UNIX_EPOCH),
+            None => bail!("Path {:?} does not exist", path),
+        }
+    }
+
+    fn file_size(&self, path: &Path) -> Result<u64> {
+        match self.node(path, true) {
+            Some((_, Node::File { data, .. })) => Ok(data.len() as u64),
+            _ => bail!("File {:?} does not exist", path),
+        }
+    }
+
+    fn permissions(&self, path: &Path) -> Result<u32> {
+        match self.node(path, true) {
+            Some((_, Node::File { mode, .. })) => Ok(mode),
+            Some(_) => Ok(0o755),
+            None => bail!("Path {:?} does not exist", path),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let Some((resolved, Node::Dir)) = self.node(path, true) else {
+            bail!("Folder {:?} does not exist", path);
+        };
+
+        Ok(self
+            .state
+            .borrow()
+            .nodes
+            .keys()
+            .filter(|p| p.parent() == Some(resolved.as_path()))
+            .map(|p| path.join(p.file_name().unwrap()))
+            .collect())
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        match self.node(path, true) {
+            Some((resolved, _)) => Ok(resolved),
+            None => bail!("Path {:?} does not exist", path),
+        }
+    }
+
+    fn copy_file(&self, source: &Path, target: &Path) -> Result<()> {
+        self.insert_file(target, self.read(source)?)
+    }
+
+    fn rename(&self, source: &Path, target: &Path) -> Result<()> {
+        let Some((source, _)) = self.node(source, false) else {
+            bail!("Path {:?} does not exist", source);
+        };
+        let target = self.resolve(target, false)?;
+
+        let mut state = self.state.borrow_mut();
+        let moved: Vec<_> = state
+            .nodes
+            .keys()
+            .filter(|p| p.starts_with(&source))
+            .cloned()
+            .collect();
+        for path in moved {
+            let node = state.nodes.remove(&path).unwrap();
+            let relative = path.strip_prefix(&source).unwrap();
+            state.nodes.insert(target.join(relative), node);
+        }
+
+        Ok(())
+    }
+
+    fn symlink(&self, source: &Path, target: &Path) -> Result<()> {
+        let target = self.resolve(target, false)?;
+        self.state
+            .borrow_mut()
+            .nodes
+            .insert(target, Node::Symlink(source.to_path_buf()));
+
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        match self.node(path, false) {
+            Some((path, Node::File { .. } | Node::Symlink(_))) => {
+                self.state.borrow_mut().nodes.remove(&path);
+                Ok(())
+            }
+            _ => bail!("File {:?} does not exist", path),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<()> {
+        let Some((resolved, Node::Dir)) = self.node(path, false) else {
+            bail!("Folder {:?} does not exist", path);
+        };
+        if !self.read_dir(&resolved)?.is_empty() {
+            bail!("Folder {:?} is not empty", path);
+        }
+
+        self.remove_tree(&resolved);
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        let Some((resolved, _)) = self.node(path, false) else {
+            bail!("Path {:?} does not exist", path);
+        };
+
+        self.remove_tree(&resolved);
+        Ok(())
+    }
+}
+
+/// A file being written, stored in its file system when flushed.
+struct MemoryFile {
+    file_access: MemoryFileAccess,
+    path: PathBuf,
+    data: Cursor<Vec<u8>>,
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.data.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file_access
+            .insert_file(&self.path, self.data.get_ref().clone())
+            .map_err(std::io::Error::other)
+    }
+}
+
+impl Seek for MemoryFile {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        self.data.seek(position)
+    }
+}
+
+/// A console answering every confirmation the same way, and recording messages.
+#[derive(Default)]
+pub struct FakeConsole {
+    confirm: bool,
+    pub messages: RefCell<Vec<(MessageLevel, String)>>,
+}
+
+impl FakeConsole {
+    pub fn confirming() -> Self {
+        Self {
+            confirm: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl Console for FakeConsole {
+    fn confirm(&self, _message: &str) -> Result<bool> {
+        Ok(self.confirm)
+    }
+
+    fn message(&self, level: &MessageLevel, message: &str, _pause: bool) -> Result<()> {
+        self.messages
+            .borrow_mut()
+            .push((level.clone(), message.to_string()));
+        Ok(())
+    }
+
+    fn checklist(&self, _title: &str, items: &[String], _blocking: bool) -> Result<Vec<bool>> {
+        Ok(vec![self.confirm; items.len()])
+    }
+}
+
+/// An executor recording intents instead of running them.
+///
+/// Commands whose target and arguments contain every word of a failure
+/// pattern fail, and captured commands return the queued outputs in order.
+#[derive(Default)]
+pub struct RecordingExecutor {
+    pub intents: RefCell<Vec<CommandIntent>>,
+    failures: Vec<String>,
+    outputs: RefCell<Vec<CommandOutput>>,
+}
+
+impl RecordingExecutor {
+    pub fn failing_on(mut self, pattern: &str) -> Self {
+        self.failures.push(pattern.into());
+        self
+    }
+
+    pub fn with_output(self, success: bool, stdout: &str) -> Self {
+        self.outputs.borrow_mut().push(CommandOutput {
+            success,
+            stdout: stdout.into(),
+            stderr: String::new(),
+        });
+        self
+    }
+
+    /// Recorded commands, as `target arg1 arg2` lines.
+    pub fn commands(&self) -> Vec<String> {
+        self.intents.borrow().iter().map(command_line).collect()
+    }
+
+    fn record(&self, intent: CommandIntent) -> Result<()> {
+        let line = command_line(&intent);
+        self.intents.borrow_mut().push(intent);
+
+        if let Some(pattern) = self
+            .failures
+            .iter()
+            .find(|p| p.split(' ').all(|word| line.split(' ').any(|w| w == word)))
+        {
+            bail!("Command failed on '{pattern}': {line}");
+        }
+
+        Ok(())
+    }
+}
+
+impl CommandExecutor for RecordingExecutor {
+    fn execute(&self, intent: CommandIntent) -> Result<()> {
+        self.record(intent)
+    }
+
+    fn capture(&self, intent: CommandIntent) -> Result<CommandOutput> {
+        self.record(intent)?;
+
+        let mut outputs = self.outputs.borrow_mut();
+        if outputs.is_empty() {
+            return Ok(CommandOutput {
+                success: true,
+                ..Default::default()
+            });
+        }
+
+        Ok(outputs.remove(0))
+    }
+}
+
+fn command_line(intent: &CommandIntent) -> String {
+    match intent {
+        CommandIntent::System { target, .. } => format!("open {}", target.to_string_lossy()),
+        CommandIntent::Custom {
+            target, arguments, ..
+        } => std::iter::once(target.to_string_lossy().to_string())
+            .chain(arguments.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// A clock starting at the Unix epoch, and only moving forward when slept on.
+pub struct FakeClock {
+    now: Cell<SystemTime>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self {
+            now: Cell::new(UNIX_EPOCH),
+        }
+    }
+}
+
+impl FakeClock {
+    pub fn elapsed(&self) -> Duration {
+        self.now.get().duration_since(UNIX_EPOCH).unwrap()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::Clock;
+
+/// Parse a duration like `500ms`, `90s`, `25m`, `1h` or `1h30m`.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    let mut chars = value.trim().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let amount: u64 = number
+            .parse()
+            .map_err(|_| eyre!("Invalid duration '{value}'"))?;
+        number.clear();
+
+        let factor = match c {
+            'h' => 3_600_000,
+            'm' if chars.next_if_eq(&'s').is_some() => 1,
+            'm' => 60_000,
+            's' => 1000,
+            _ => return Err(eyre!("Invalid duration unit '{c}' in '{value}'")),
+        };
+
+        total = amount
+            .checked_mul(factor)
+            .and_then(|millis| total.checked_add(millis))
+            .ok_or_else(|| eyre!("Duration '{value}' is too long"))?;
+    }
+
+    if !number.is_empty() {
+        return Err(eyre!("Missing duration unit in '{value}'"));
+    }
+
+    // A zero duration would make the timers and watchers spin.
+    if total == 0 {
+        return Err(eyre!("Duration '{value}' must be greater than zero"));
+    }
+
+    Ok(Duration::from_millis(total))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionTimer {
+    #[serde(default = "ActionTimer::default_work")]
+    pub work: String,
+    #[serde(rename = "break", default = "ActionTimer::default_break")]
+    pub break_: String,
+    #[serde(default = "ActionTimer::default_cycles")]
+    pub cycles: u32,
+}
+
+impl ActionTimer {
+    fn default_work() -> String {
+        "25m".into()
+    }
+
+    fn default_break() -> String {
+        "5m".into()
+    }
+
+    fn default_cycles() -> u32 {
+        4
+    }
+
+    pub fn schedule(&self) -> Result<Vec<TimerPhase>> {
+        if self.cycles == 0 {
+            bail!("Timer 'cycles' must be at least 1");
+        }
+
+        let work = parse_duration(&self.work)?;
+        let break_ = parse_duration(&self.break_)?;
+
+        let mut phases = vec![];
+        for cycle in 1..=self.cycles {
+            phases.push(TimerPhase {
+                kind: TimerPhaseKind::Work,
+                cycle,
+                duration: work,
+            });
+
+            if cycle < self.cycles {
+                phases.push(TimerPhase {
+                    kind: TimerPhaseKind::Break,
+                    cycle,
+                    duration: break_,
+                });
+            }
+        }
+
+        Ok(phases)
+    }
+
+    /// Run the timer phases, calling `notify` with a title and a body at each transition.
+    pub fn run<K: Clock>(
+        &self,
+        clock: &K,
+        mut notify: impl FnMut(&str, &str) -> Result<()>,
+    ) -> Result<()> {
+        for phase in self.schedule()? {
+            let body = match phase.kind {
+                TimerPhaseKind::Work => format!(
+                    "Focus for {} (cycle {}/{})",
+                    self.work, phase.cycle, self.cycles
+                ),
+                TimerPhaseKind::Break => format!("Take a break for {}", self.break_),
+            };
+
+            notify(phase.kind.title(), &body)?;
+            clock.sleep(phase.duration);
+        }
+
+        notify(
+            "Done",
+            &format!("{} cycles completed, well done!", self.cycles),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimerPhaseKind {
+    Work,
+    Break,
+}
+
+impl TimerPhaseKind {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Work => "Focus time",
+            Self::Break => "Break time",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimerPhase {
+    pub kind: TimerPhaseKind,
+    pub cycle: u32,
+    pub duration: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeClock;
+
+    #[test]
+    fn parse_duration_combines_units() {
+        assert_eq!(
+            parse_duration("1h30m15s500ms").unwrap(),
+            Duration::from_millis(5_415_500)
+        );
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("10d").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_zero_durations() {
+        for value in ["", "  ", "0s", "0h0m0ms"] {
+            assert!(parse_duration(value).is_err(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflows() {
+        assert!(parse_duration("18446744073709551615h").is_err());
+        assert!(parse_duration("18446744073709551615ms1ms").is_err());
+    }
+
+    #[test]
+    fn schedule_rejects_zero_cycles() {
+        let timer: ActionTimer = serde_yaml::from_str("cycles: 0").unwrap();
+        let mut notifications = 0;
+
+        assert!(timer.schedule().is_err());
+        assert!(timer
+            .run(&FakeClock::default(), |_, _| {
+                notifications += 1;
+                Ok(())
+            })
+            .is_err());
+        assert_eq!(notifications, 0);
+    }
+
+    #[test]
+    fn run_notifies_each_phase_and_sleeps_on_the_clock() {
+        let timer = ActionTimer {
+            work: "25m".into(),
+            break_: "5m".into(),
+            cycles: 2,
+        };
+        let clock = FakeClock::default();
+        let mut titles = vec![];
+
+        timer
+            .run(&clock, |title, _| {
+                titles.push(title.to_string());
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(titles, ["Focus time", "Break time", "Focus time", "Done"]);
+        assert_eq!(clock.elapsed(), Duration::from_secs(55 * 60));
+    }
+}
@@ -0,0 +1,313 @@
+use std::path::PathBuf;
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    context::Context, intent::CommandIntent, resolvable::Resolvable, CommandExecutor, System,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TmuxPane {
+    pub command: Option<String>,
+    pub working_directory: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TmuxWindow {
+    pub name: String,
+    pub layout: Option<String>,
+    pub working_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub panes: Vec<TmuxPane>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionTmux {
+    pub session: String,
+    pub working_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub windows: Vec<TmuxWindow>,
+}
+
+impl ActionTmux {
+    pub(crate) fn execute<E: CommandExecutor>(&self, system: &System, executor: &E) -> Result<()> {
+        let inside_tmux = std::env::var_os("TMUX").is_some();
+        self.open(system, executor, inside_tmux)
+    }
+
+    fn open<E: CommandExecutor>(
+        &self,
+        system: &System,
+        executor: &E,
+        inside_tmux: bool,
+    ) -> Result<()> {
+        let session_exists = executor
+            .capture(system.run_tmux(vec![
+                "has-session".into(),
+                "-t".into(),
+                format!("={}", self.session),
+            ]))?
+            .success;
+
+        if session_exists {
+            info!(
+                message = "Tmux session already exists",
+                session = ?self.session
+            );
+        } else {
+            for intent in self.create_session_intents(system) {
+                let output = executor
+                    .capture(intent)
+                    .map_err(|e| eyre!("Could not create tmux session '{}': {e}", self.session))?;
+                if !output.success {
+                    bail!(
+                        "Could not create tmux session '{}': {}",
+                        self.session,
+                        output.stderr.trim()
+                    );
+                }
+            }
+        }
+
+        executor.execute(self.attach_intent(system, inside_tmux))
+    }
+
+    pub fn create_session_intents(&self, system: &System) -> Vec<CommandIntent> {
+        let mut intents = vec![];
+
+        let default_window = TmuxWindow {
+            name: self.session.clone(),
+            layout: None,
+            working_directory: None,
+            panes: vec![],
+        };
+        let windows = if self.windows.is_empty() {
+            std::slice::from_ref(&default_window)
+        } else {
+            &self.windows
+        };
+
+        for (window_index, window) in windows.iter().enumerate() {
+            let window_target = format!("={}:{}", self.session, window.name);
+            let window_directory = window
+                .working_directory
+                .as_ref()
+                .or(self.working_directory.as_ref());
+
+            let default_pane = TmuxPane {
+                command: None,
+                working_directory: None,
+            };
+            let panes = if window.panes.is_empty() {
+                std::slice::from_ref(&default_pane)
+            } else {
+                &window.panes
+            };
+
+            for (pane_index, pane) in panes.iter().enumerate() {
+                let mut arguments = match (window_index, pane_index) {
+                    (0, 0) => vec![
+                        "new-session".into(),
+                        "-d".into(),
+                        "-s".into(),
+                        self.session.clone(),
+                        "-n".into(),
+                        window.name.clone(),
+                    ],
+                    (_, 0) => vec![
+                        "new-window".into(),
+                        "-t".into(),
+                        format!("={}", self.session),
+                        "-n".into(),
+                        window.name.clone(),
+                    ],
+                    _ => vec!["split-window".into(), "-t".into(), window_target.clone()],
+                };
+
+                if let Some(dir) = pane.working_directory.as_ref().or(window_directory) {
+                    arguments.extend(["-c".into(), dir.to_string_lossy().to_string()]);
+                }
+
+                intents.push(system.run_tmux(arguments));
+
+                // New panes are focused, so keys are sent to the pane we just created.
+                if let Some(command) = &pane.command {
+                    intents.push(system.run_tmux(vec![
+                        "send-keys".into(),
+                        "-t".into(),
+                        window_target.clone(),
+                        command.clone(),
+                        "Enter".into(),
+                    ]));
+                }
+            }
+
+            if let Some(layout) = &window.layout {
+                intents.push(system.run_tmux(vec![
+                    "select-layout".into(),
+                    "-t".into(),
+                    window_target.clone(),
+                    layout.clone(),
+                ]));
+            }
+        }
+
+        intents
+    }
+
+    pub fn attach_intent(&self, system: &System, inside_tmux: bool) -> CommandIntent {
+        let command = if inside_tmux {
+            "switch-client"
+        } else {
+            "attach-session"
+        };
+
+        system.run_tmux(vec![
+            command.into(),
+            "-t".into(),
+            format!("={}", self.session),
+        ])
+    }
+
+    pub(crate) fn to_pretty_string(&self) -> String {
+        let windows = self
+            .windows
+            .iter()
+            .map(|w| w.name.as_str())
+            .collect::<Vec<_>>();
+        if windows.is_empty() {
+            format!("Open tmux session \"{}\"", self.session)
+        } else {
+            format!(
+                "Open tmux session \"{}\" with windows [{}]",
+                self.session,
+                windows.join(", ")
+            )
+        }
+    }
+}
+
+impl Resolvable for ActionTmux {
+    fn resolve(&mut self, ctx: &Context) {
+        self.session.resolve(ctx);
+        self.working_directory.resolve(ctx);
+        for window in &mut self.windows {
+            window.name.resolve(ctx);
+            window.working_directory.resolve(ctx);
+            for pane in &mut window.panes {
+                pane.command.resolve(ctx);
+                pane.working_directory.resolve(ctx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RecordingExecutor;
+
+    fn action() -> ActionTmux {
+        serde_yaml::from_str(
+            r#"
+            session: dev
+            working_directory: /code
+            windows:
+              - name: editor
+                panes:
+                  - command: nvim
+              - name: logs
+                layout: tiled
+                working_directory: /var/log
+                panes:
+                  - command: tail -f app.log
+                  - working_directory: /tmp
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn create_session_intents_build_the_layout() {
+        let executor = RecordingExecutor::default();
+        for intent in action().create_session_intents(&System::default()) {
+            executor.execute(intent).unwrap();
+        }
+
+        assert_eq!(
+            executor.commands(),
+            [
+                "tmux new-session -d -s dev -n editor -c /code",
+                "tmux send-keys -t =dev:editor nvim Enter",
+                "tmux new-window -t =dev -n logs -c /var/log",
+                "tmux send-keys -t =dev:logs tail -f app.log Enter",
+                "tmux split-window -t =dev:logs -c /tmp",
+                "tmux select-layout -t =dev:logs tiled",
+            ]
+        );
+    }
+
+    #[test]
+    fn open_creates_missing_sessions_then_attaches() {
+        let executor = RecordingExecutor::default().with_output(false, "");
+        let action: ActionTmux = serde_yaml::from_str("session: dev").unwrap();
+
+        action.open(&System::default(), &executor, false).unwrap();
+
+        assert_eq!(
+            executor.commands(),
+            [
+                "tmux has-session -t =dev",
+                "tmux new-session -d -s dev -n dev",
+                "tmux attach-session -t =dev",
+            ]
+        );
+    }
+
+    #[test]
+    fn open_switches_to_existing_sessions_from_tmux() {
+        let executor = RecordingExecutor::default().with_output(true, "");
+
+        action().open(&System::default(), &executor, true).unwrap();
+
+        assert_eq!(
+            executor.commands(),
+            ["tmux has-session -t =dev", "tmux switch-client -t =dev"]
+        );
+    }
+
+    #[test]
+    fn open_stops_when_a_tmux_command_fails() {
+        let executor = RecordingExecutor::default()
+            .with_output(false, "")
+            .failing_on("tmux split-window");
+
+        assert!(action().open(&System::default(), &executor, false).is_err());
+        assert!(!executor
+            .commands()
+            .iter()
+            .any(|c| c.starts_with("tmux attach-session")));
+    }
+
+    #[test]
+    fn open_stops_when_a_tmux_command_exits_with_an_error() {
+        let executor = RecordingExecutor::default()
+            .with_output(false, "")
+            .with_output(false, "");
+        let action: ActionTmux = serde_yaml::from_str("session: dev").unwrap();
+
+        assert!(action.open(&System::default(), &executor, false).is_err());
+        assert_eq!(
+            executor.commands(),
+            [
+                "tmux has-session -t =dev",
+                "tmux new-session -d -s dev -n dev"
+            ]
+        );
+    }
+}
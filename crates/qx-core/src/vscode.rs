@@ -0,0 +1,73 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{context::Context, resolvable::Resolvable};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VSCodeWindow {
+    New,
+    Reuse,
+}
+
+impl VSCodeWindow {
+    pub(crate) fn as_argument(&self) -> &'static str {
+        match self {
+            Self::New => "--new-window",
+            Self::Reuse => "--reuse-window",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VSCodeRemote {
+    Ssh { host: String },
+    Wsl { distribution: String },
+    DevContainer { local_folder: PathBuf },
+}
+
+impl VSCodeRemote {
+    pub(crate) fn build_uri(&self, path: &Path) -> String {
+        let authority = match self {
+            Self::Ssh { host } => format!("ssh-remote+{host}"),
+            Self::Wsl { distribution } => format!("wsl+{distribution}"),
+            Self::DevContainer { local_folder } => {
+                // Dev containers are identified by their hex-encoded local folder.
+                let mut authority = "dev-container+".to_string();
+                for byte in local_folder.to_string_lossy().bytes() {
+                    write!(authority, "{byte:02x}").unwrap();
+                }
+
+                authority
+            }
+        };
+
+        let path = path.to_string_lossy().replace('\\', "/");
+        if path.starts_with('/') {
+            format!("vscode-remote://{authority}{path}")
+        } else {
+            format!("vscode-remote://{authority}/{path}")
+        }
+    }
+
+    pub(crate) fn to_pretty_string(&self) -> String {
+        match self {
+            Self::Ssh { host } => format!("SSH host \"{host}\""),
+            Self::Wsl { distribution } => format!("WSL distribution \"{distribution}\""),
+            Self::DevContainer { local_folder } => format!("dev container for {local_folder:?}"),
+        }
+    }
+}
+
+impl Resolvable for VSCodeRemote {
+    fn resolve(&mut self, ctx: &Context) {
+        match self {
+            Self::Ssh { host } => host.resolve(ctx),
+            Self::Wsl { distribution } => distribution.resolve(ctx),
+            Self::DevContainer { local_folder } => local_folder.resolve(ctx),
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use color_eyre::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    actions::Action, context::Context, one_or_many::OneOrMany, resolvable::Resolvable,
+    timer::parse_duration, Clock, FileAccess,
+};
+
+type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionWatch {
+    pub paths: OneOrMany<PathBuf>,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    #[serde(default = "ActionWatch::default_debounce")]
+    pub debounce: String,
+    #[serde(default = "ActionWatch::default_interval")]
+    pub interval: String,
+    pub actions: Vec<Action>,
+}
+
+struct Filters {
+    include: Option<GlobSet>,
+    ignore: GlobSet,
+}
+
+impl ActionWatch {
+    fn default_debounce() -> String {
+        "500ms".into()
+    }
+
+    fn default_interval() -> String {
+        "1s".into()
+    }
+
+    /// Check the durations and patterns, to report errors before detaching.
+    pub fn validate(&self) -> Result<()> {
+        parse_duration(&self.debounce)?;
+        parse_duration(&self.interval)?;
+        self.filters()?;
+
+        Ok(())
+    }
+
+    pub fn to_pretty_string(&self) -> String {
+        format!(
+            "Watch [{}] and run {} actions on changes",
+            self.paths
+                .as_slice()
+                .iter()
+                .map(|p| format!("{p:?}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.actions.len()
+        )
+    }
+
+    /// Poll the watched paths forever, calling `on_change` with the changed
+    /// paths once they stop changing for the debounce duration.
+    pub fn run<F: FileAccess, K: Clock>(
+        &self,
+        file_access: &F,
+        clock: &K,
+        mut on_change: impl FnMut(&[PathBuf]) -> Result<()>,
+    ) -> Result<()> {
+        let debounce = parse_duration(&self.debounce)?;
+        let interval = parse_duration(&self.interval)?;
+        let filters = self.filters()?;
+
+        let mut snapshot = self.scan(file_access, &filters);
+        loop {
+            clock.sleep(interval);
+
+            let mut current = self.scan(file_access, &filters);
+            if current == snapshot {
+                continue;
+            }
+
+            // Wait for the changes to settle.
+            loop {
+                clock.sleep(debounce);
+                let next = self.scan(file_access, &filters);
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+
+            let changes = changed_paths(&snapshot, &current);
+            snapshot = current;
+
+            info!(
+                message = "Watched paths changed",
+                changes = ?changes
+            );
+
+            if let Err(error) = on_change(&changes) {
+                warn!(
+                    message = "Watch actions failed",
+                    error = ?error
+                );
+            }
+        }
+    }
+
+    fn filters(&self) -> Result<Filters> {
+        Ok(Filters {
+            include: if self.include.is_empty() {
+                None
+            } else {
+                Some(build_glob_set(&self.include)?)
+            },
+            ignore: build_glob_set(&self.ignore)?,
+        })
+    }
+
+    fn scan<F: FileAccess>(&self, file_access: &F, filters: &Filters) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        for root in self.paths.as_slice() {
+            scan_path(file_access, filters, root, root, &mut snapshot);
+        }
+
+        snapshot
+    }
+}
+
+fn scan_path<F: FileAccess>(
+    file_access: &F,
+    filters: &Filters,
+    root: &Path,
+    path: &Path,
+    snapshot: &mut Snapshot,
+) {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    if path != root && filters.ignore.is_match(relative) {
+        return;
+    }
+
+    if file_access.is_dir(path) {
+        // Linked folders can point to one of their parents, only watched roots are followed.
+        if path != root && file_access.is_symlink(path) {
+            return;
+        }
+
+        for child in file_access.read_dir(path).unwrap_or_default() {
+            scan_path(file_access, filters, root, &child, snapshot);
+        }
+        return;
+    }
+
+    let included = path == root
+        || filters
+            .include
+            .as_ref()
+            .map_or(true, |include| include.is_match(relative));
+    if !included {
+        return;
+    }
+
+    // Files can disappear while scanning, they will show up as removed.
+    if let Ok(modified) = file_access.modified(path) {
+        snapshot.insert(path.to_path_buf(), modified);
+    }
+}
+
+pub(crate) fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
+
+fn changed_paths(previous: &Snapshot, current: &Snapshot) -> Vec<PathBuf> {
+    let mut changes: Vec<_> = current
+        .iter()
+        .filter(|(path, modified)| previous.get(*path) != Some(*modified))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changes.extend(
+        previous
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned(),
+    );
+
+    changes
+}
+
+impl Resolvable for ActionWatch {
+    fn resolve(&mut self, ctx: &Context) {
+        // Nested actions are resolved just before their execution.
+        self.paths.resolve(ctx);
+        self.include.resolve(ctx);
+        self.ignore.resolve(ctx);
+        self.debounce.resolve(ctx);
+        self.interval.resolve(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MemoryFileAccess;
+
+    fn watch(yaml: &str) -> ActionWatch {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn validate_rejects_empty_intervals() {
+        assert!(watch("{paths: /code, interval: '', actions: []}")
+            .validate()
+            .is_err());
+        assert!(watch("{paths: /code, debounce: 0ms, actions: []}")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn scan_skips_linked_folders() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/code/src/main.rs", "")
+            .with_file("/shared/lib.rs", "")
+            .with_symlink("/code", "/code/src/loop")
+            .with_symlink("/shared", "/code/shared")
+            .with_symlink("/shared/lib.rs", "/code/lib.rs");
+        let action = watch("{paths: /code, actions: []}");
+
+        let snapshot = action.scan(&file_access, &action.filters().unwrap());
+
+        assert_eq!(
+            snapshot.keys().collect::<Vec<_>>(),
+            [Path::new("/code/lib.rs"), Path::new("/code/src/main.rs")]
+        );
+    }
+
+    #[test]
+    fn scan_follows_linked_roots() {
+        let file_access = MemoryFileAccess::default()
+            .with_file("/shared/lib.rs", "")
+            .with_symlink("/shared", "/code");
+        let action = watch("{paths: /code, include: ['*.rs'], actions: []}");
+
+        let snapshot = action.scan(&file_access, &action.filters().unwrap());
+
+        assert_eq!(
+            snapshot.keys().collect::<Vec<_>>(),
+            [Path::new("/code/lib.rs")]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use qx_core::{ChecklistOutcome, FileAccess};
+
+/// Number of boots kept in the history.
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootEntry {
+    pub environment: String,
+    pub booted_at: u64,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklists: Vec<ChecklistOutcome>,
+}
+
+/// Keeps track of the last environment boots.
+pub struct BootHistory<'a, F: FileAccess> {
+    pub file_access: &'a F,
+}
+
+impl<'a, F: FileAccess> BootHistory<'a, F> {
+    pub fn new(file_access: &'a F) -> Self {
+        Self { file_access }
+    }
+
+    pub fn get_history_path(&self) -> PathBuf {
+        self.file_access
+            .user_data_directory()
+            .join("qx")
+            .join("history.yaml")
+    }
+
+    pub fn list(&self) -> Result<Vec<BootEntry>> {
+        let path = self.get_history_path();
+        if !self.file_access.file_exists(&path) {
+            return Ok(vec![]);
+        }
+
+        let data = self.file_access.read_to_string(&path)?;
+        Ok(serde_yaml::from_str::<Option<Vec<BootEntry>>>(&data)?.unwrap_or_default())
+    }
+
+    pub fn record(&self, entry: BootEntry) -> Result<()> {
+        info!(
+            message = "Recording environment boot",
+            environment = ?entry.environment,
+            success = entry.success
+        );
+
+        let mut entries = self.list()?;
+        entries.push(entry);
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+
+        let path = self.get_history_path();
+        if let Some(parent_dir) = path.parent() {
+            self.file_access.create_dir_all(parent_dir)?;
+        }
+
+        self.file_access
+            .write(&path, serde_yaml::to_string(&entries)?)
+    }
+}
@@ -1,6 +1,9 @@
-mod io;
+mod boot_history;
 mod models;
+mod process_registry;
 mod storage;
 
-pub use io::{FileAccess, FileAccessIo};
+pub use boot_history::{BootEntry, BootHistory};
+pub use process_registry::{ProcessEntry, ProcessRegistry};
+pub use qx_core::{FileAccess, FileAccessIo};
 pub use storage::ConfigurationStorage;
@@ -1,19 +1,31 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use qx_core::{Action, System};
+use qx_core::{Action, System, Variable};
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub(crate) struct ConfigurationModel {
     pub version: String,
     pub system: Option<System>,
-    pub variables: Option<HashMap<String, String>>,
+    pub variables: Option<HashMap<String, Variable>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dotenv: Option<Vec<PathBuf>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub export_dotenv: bool,
     pub environments: Option<HashMap<String, EnvironmentModel>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub(crate) struct EnvironmentModel {
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dotenv: Option<Vec<PathBuf>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub export_dotenv: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cd: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export: Option<HashMap<String, String>>,
     pub actions: Option<Vec<Action>>,
 }
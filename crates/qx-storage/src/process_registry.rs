@@ -0,0 +1,105 @@
+use std::{path::PathBuf, time::Duration};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use qx_core::{Clock, FileAccess};
+
+/// How long to wait for another qx process to release the registry.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub environment: Option<String>,
+    pub description: String,
+    pub started_at: u64,
+}
+
+/// Keeps track of the background processes spawned by qx.
+///
+/// Background processes update the registry concurrently, so changes are
+/// made while holding a lock file next to it.
+pub struct ProcessRegistry<'a, F: FileAccess, K: Clock> {
+    pub file_access: &'a F,
+    pub clock: &'a K,
+}
+
+impl<'a, F: FileAccess, K: Clock> ProcessRegistry<'a, F, K> {
+    pub fn new(file_access: &'a F, clock: &'a K) -> Self {
+        Self { file_access, clock }
+    }
+
+    pub fn get_registry_path(&self) -> PathBuf {
+        self.file_access
+            .user_data_directory()
+            .join("qx")
+            .join("processes.yaml")
+    }
+
+    pub fn list(&self) -> Result<Vec<ProcessEntry>> {
+        let path = self.get_registry_path();
+        if !self.file_access.file_exists(&path) {
+            return Ok(vec![]);
+        }
+
+        let data = self.file_access.read_to_string(&path)?;
+        Ok(serde_yaml::from_str::<Option<Vec<ProcessEntry>>>(&data)?.unwrap_or_default())
+    }
+
+    pub fn register(&self, entry: ProcessEntry) -> Result<()> {
+        info!(
+            message = "Registering background process",
+            pid = entry.pid,
+            description = ?entry.description
+        );
+
+        self.update(|entries| {
+            entries.retain(|e| e.pid != entry.pid);
+            entries.push(entry);
+        })
+    }
+
+    pub fn unregister(&self, pid: u32) -> Result<()> {
+        info!(message = "Unregistering background process", pid = pid);
+
+        self.update(|entries| entries.retain(|e| e.pid != pid))
+    }
+
+    /// Forget about processes which are not running anymore.
+    pub fn forget(&self, pids: &[u32]) -> Result<()> {
+        self.update(|entries| entries.retain(|e| !pids.contains(&e.pid)))
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Vec<ProcessEntry>)) -> Result<()> {
+        let path = self.get_registry_path();
+        if let Some(parent_dir) = path.parent() {
+            self.file_access.create_dir_all(parent_dir)?;
+        }
+
+        let lock_path = path.with_extension("lock");
+        let deadline = self.clock.now() + LOCK_TIMEOUT;
+        while !self.file_access.create_new(&lock_path)? {
+            if self.clock.now() >= deadline {
+                // The lock was left behind by a process which did not release it.
+                warn!(
+                    message = "Taking over the process registry lock",
+                    path = ?lock_path
+                );
+                break;
+            }
+
+            self.clock.sleep(Duration::from_millis(50));
+        }
+
+        let result = self.list().and_then(|mut entries| {
+            change(&mut entries);
+            self.file_access
+                .write(&path, serde_yaml::to_string(&entries)?)
+        });
+        self.file_access.remove_file(&lock_path)?;
+
+        result
+    }
+}
@@ -1,13 +1,10 @@
 use color_eyre::Result;
 use std::path::{Path, PathBuf};
 
-use qx_core::{Configuration, Context, Environment, Resolvable};
+use qx_core::{Configuration, Context, Environment, FileAccess, Resolvable};
 use tracing::{debug, info};
 
-use crate::{
-    io::FileAccess,
-    models::{ConfigurationModel, EnvironmentModel},
-};
+use crate::models::{ConfigurationModel, EnvironmentModel};
 
 pub struct ConfigurationStorage<'a, F: FileAccess> {
     pub file_access: &'a F,
@@ -72,7 +69,9 @@ impl<'a, F: FileAccess> ConfigurationStorage<'a, F> {
         ConfigurationModel {
             version: env!("CARGO_PKG_VERSION").to_string(),
             system: Some(configuration.system.clone()),
-            variables: Some(configuration.variables.0.clone()),
+            variables: Some(configuration.variables.to_variables()),
+            dotenv: non_empty(&configuration.dotenv),
+            export_dotenv: configuration.export_dotenv,
             environments: Some(
                 configuration
                     .environments
@@ -86,7 +85,9 @@ impl<'a, F: FileAccess> ConfigurationStorage<'a, F> {
     fn configuration_from_serde_model(&self, model: ConfigurationModel) -> Configuration {
         Configuration {
             system: model.system.unwrap_or_default(),
-            variables: Context::new(model.variables.unwrap_or_default()),
+            variables: Context::from_variables(model.variables.unwrap_or_default()),
+            dotenv: model.dotenv.unwrap_or_default(),
+            export_dotenv: model.export_dotenv,
             environments: model
                 .environments
                 .unwrap_or_default()
@@ -99,6 +100,14 @@ impl<'a, F: FileAccess> ConfigurationStorage<'a, F> {
     fn environment_to_serde_model(&self, environment: &Environment) -> EnvironmentModel {
         EnvironmentModel {
             description: environment.description.clone(),
+            dotenv: non_empty(&environment.dotenv),
+            export_dotenv: environment.export_dotenv,
+            cd: environment.cd.clone(),
+            export: if environment.export.is_empty() {
+                None
+            } else {
+                Some(environment.export.clone())
+            },
             actions: Some(environment.actions.to_vec()),
         }
     }
@@ -107,7 +116,50 @@ impl<'a, F: FileAccess> ConfigurationStorage<'a, F> {
         Environment {
             name,
             description: model.description,
+            dotenv: model.dotenv.unwrap_or_default(),
+            export_dotenv: model.export_dotenv,
+            cd: model.cd,
+            export: model.export.unwrap_or_default(),
             actions: model.actions.unwrap_or_default(),
         }
     }
 }
+
+/// Dotenv files to store, omitted when there are none.
+fn non_empty(paths: &[PathBuf]) -> Option<Vec<PathBuf>> {
+    if paths.is_empty() {
+        None
+    } else {
+        Some(paths.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use qx_core::FileAccessIo;
+
+    use super::*;
+
+    #[test]
+    fn empty_settings_are_not_written() {
+        let file_access = FileAccessIo::default();
+        let storage = ConfigurationStorage::new(&file_access);
+        let environment = Environment {
+            name: "dev".into(),
+            description: "Development".into(),
+            actions: vec![],
+            dotenv: vec![],
+            export_dotenv: false,
+            cd: None,
+            export: HashMap::new(),
+        };
+
+        let yaml =
+            serde_yaml::to_string(&storage.environment_to_serde_model(&environment)).unwrap();
+        for key in ["dotenv", "cd", "export"] {
+            assert!(!yaml.contains(key), "{yaml}");
+        }
+    }
+}
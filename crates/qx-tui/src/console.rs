@@ -0,0 +1,272 @@
+use std::{cell::RefCell, io::stdout};
+
+use color_eyre::{eyre::bail, Result};
+use crossterm::event::{self, Event, KeyCode};
+use qx_core::{parse_message, Console, MessageLevel, MessageLine, MessageSpan};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, Padding, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::{stateful_list::StatefulList, TerminalGuard};
+
+/// A [`Console`] drawing its prompts with the terminal UI.
+///
+/// Messages are kept until the next prompt, a paused message or [`TuiConsole::finish`],
+/// and then shown together in a pane.
+#[derive(Default)]
+pub struct TuiConsole {
+    messages: RefCell<Vec<(MessageLevel, String)>>,
+}
+
+impl TuiConsole {
+    /// Show the pending messages, if any, and wait for the user.
+    pub fn finish(&self) -> Result<()> {
+        if self.messages.borrow().is_empty() {
+            return Ok(());
+        }
+
+        self.show_messages("ENTER - Close")
+    }
+
+    fn show_messages(&self, status: &str) -> Result<()> {
+        let messages = self.messages.take();
+
+        self.with_terminal(|terminal| loop {
+            terminal.draw(|frame| {
+                let area = render_status(frame, status);
+                render_messages(frame, area, &messages);
+            })?;
+
+            if let Some(KeyCode::Enter) = read_key()? {
+                return Ok(());
+            }
+        })
+    }
+
+    fn with_terminal<T>(
+        &self,
+        run: impl FnOnce(&mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<T>,
+    ) -> Result<T> {
+        let _guard = TerminalGuard::enter()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+        run(&mut terminal)
+    }
+}
+
+impl Console for TuiConsole {
+    fn confirm(&self, message: &str) -> Result<bool> {
+        let messages = self.messages.take();
+
+        self.with_terminal(|terminal| loop {
+            terminal.draw(|frame| {
+                let paragraph = Paragraph::new(message)
+                    .block(
+                        Block::default()
+                            .title("Confirm")
+                            .borders(Borders::ALL)
+                            .padding(Padding::uniform(1)),
+                    )
+                    .wrap(Wrap { trim: true });
+
+                let area = render_status(frame, "Y - Yes    N - No");
+                let area = split_messages(frame, area, &messages);
+                frame.render_widget(paragraph, area);
+            })?;
+
+            if let Some(code) = read_key()? {
+                match code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(true),
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => return Ok(false),
+                    _ => (),
+                }
+            }
+        })
+    }
+
+    fn message(&self, level: &MessageLevel, message: &str, pause: bool) -> Result<()> {
+        self.messages
+            .borrow_mut()
+            .push((level.clone(), message.to_string()));
+
+        if pause {
+            self.show_messages("ENTER - Continue")?;
+        }
+
+        Ok(())
+    }
+
+    fn checklist(&self, title: &str, items: &[String], blocking: bool) -> Result<Vec<bool>> {
+        let messages = self.messages.take();
+        let mut acknowledged = vec![false; items.len()];
+        let mut list = StatefulList::new(items.to_vec());
+        list.select_first_if_exists();
+
+        let status = if blocking {
+            "UP/DOWN - Move cursor    SPACE - Check    A - Check all    ENTER - Continue once done    ESC - Abort"
+        } else {
+            "UP/DOWN - Move cursor    SPACE - Check    A - Check all    ENTER - Continue    ESC - Abort"
+        };
+
+        self.with_terminal(|terminal| loop {
+            terminal.draw(|frame| {
+                let lines: Vec<_> = items
+                    .iter()
+                    .zip(&acknowledged)
+                    .map(|(item, done)| format!("[{}] {item}", if *done { "x" } else { " " }))
+                    .collect();
+
+                let widget = List::new(lines)
+                    .block(
+                        Block::default()
+                            .title(title)
+                            .borders(Borders::ALL)
+                            .padding(Padding::uniform(1)),
+                    )
+                    .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                    .highlight_symbol(">> ");
+
+                let area = render_status(frame, status);
+                let area = split_messages(frame, area, &messages);
+                frame.render_stateful_widget(widget, area, list.state_mut());
+            })?;
+
+            if let Some(code) = read_key()? {
+                match code {
+                    KeyCode::Up => list.select_previous(),
+                    KeyCode::Down => list.select_next(),
+                    KeyCode::Char(' ') => {
+                        if let Some(selected) = list.selected_index() {
+                            acknowledged[selected] = !acknowledged[selected];
+                        }
+                    }
+                    KeyCode::Char('a') => acknowledged.fill(true),
+                    KeyCode::Enter if !blocking || acknowledged.iter().all(|a| *a) => {
+                        return Ok(acknowledged.clone())
+                    }
+                    KeyCode::Esc => bail!("Checklist '{title}' was aborted"),
+                    _ => (),
+                }
+            }
+        })
+    }
+}
+
+fn read_key() -> Result<Option<KeyCode>> {
+    if event::poll(std::time::Duration::from_millis(50))? {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == event::KeyEventKind::Press {
+                return Ok(Some(key.code));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Render a status line at the bottom of the frame, and return the remaining area.
+fn render_status(frame: &mut Frame, status: &str) -> Rect {
+    let areas = Layout::new(
+        Direction::Vertical,
+        [Constraint::Min(0), Constraint::Length(1)],
+    )
+    .split(frame.size());
+
+    frame.render_widget(
+        Paragraph::new(status).alignment(Alignment::Center),
+        areas[1],
+    );
+
+    areas[0]
+}
+
+/// Render the messages at the top of the area if there are any, and return the remaining area.
+fn split_messages(frame: &mut Frame, area: Rect, messages: &[(MessageLevel, String)]) -> Rect {
+    if messages.is_empty() {
+        return area;
+    }
+
+    let areas = Layout::new(
+        Direction::Vertical,
+        [Constraint::Percentage(40), Constraint::Percentage(60)],
+    )
+    .split(area);
+
+    render_messages(frame, areas[0], messages);
+    areas[1]
+}
+
+fn render_messages(frame: &mut Frame, area: Rect, messages: &[(MessageLevel, String)]) {
+    let mut lines = vec![];
+    for (level, message) in messages {
+        let tag = format!("[{}]", level.label());
+        let indent = " ".repeat(tag.len());
+
+        for (index, line) in parse_message(message).iter().enumerate() {
+            let mut spans = vec![if index == 0 {
+                Span::styled(
+                    tag.clone(),
+                    Style::new()
+                        .fg(level_color(level))
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::raw(indent.clone())
+            }];
+
+            match line {
+                MessageLine::Text(content) => {
+                    spans.push(Span::raw(" "));
+                    spans.extend(content.iter().flat_map(styled_spans));
+                }
+                MessageLine::ListItem(content) => {
+                    spans.push(Span::raw(" • "));
+                    spans.extend(content.iter().flat_map(styled_spans));
+                }
+            }
+
+            lines.push(Line::from(spans));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Messages")
+                .borders(Borders::ALL)
+                .padding(Padding::uniform(1)),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(paragraph, area);
+}
+
+fn styled_spans(span: &MessageSpan) -> Vec<Span<'static>> {
+    let style = if span.bold {
+        Style::new().add_modifier(Modifier::BOLD)
+    } else {
+        Style::new()
+    };
+
+    match &span.link {
+        Some(link) => vec![
+            Span::styled(span.text.clone(), style.add_modifier(Modifier::UNDERLINED)),
+            Span::styled(format!(" ({link})"), style),
+        ],
+        None => vec![Span::styled(span.text.clone(), style)],
+    }
+}
+
+fn level_color(level: &MessageLevel) -> Color {
+    match level {
+        MessageLevel::Info => Color::Blue,
+        MessageLevel::Success => Color::Green,
+        MessageLevel::Warn => Color::Yellow,
+        MessageLevel::Error => Color::Red,
+    }
+}
@@ -1,6 +1,7 @@
+mod console;
 mod stateful_list;
 
-use std::io::stdout;
+use std::{collections::HashSet, io::stdout};
 
 use color_eyre::Result;
 use crossterm::{
@@ -9,19 +10,63 @@ use crossterm::{
     ExecutableCommand,
 };
 use itertools::Itertools;
-use qx_core::{banner, Configuration, Environment};
+use qx_core::{banner, Action, Configuration, Context, Environment, ResolvableClone};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Modifier, Style},
+    text::Line,
     widgets::{Block, Borders, List, Padding, Paragraph, Wrap},
     Frame, Terminal,
 };
 
+pub use console::TuiConsole;
 use stateful_list::StatefulList;
 
 struct State {
     environments: StatefulList,
+    actions_focused: bool,
+    action_cursor: usize,
+    expanded_actions: HashSet<Vec<usize>>,
+}
+
+impl State {
+    fn reset_actions(&mut self) {
+        self.actions_focused = false;
+        self.action_cursor = 0;
+        self.expanded_actions.clear();
+    }
+}
+
+/// An action shown in the actions pane, with its path in the action tree.
+struct ActionNode<'a> {
+    path: Vec<usize>,
+    depth: usize,
+    action: &'a Action,
+}
+
+/// Flatten the action tree, skipping the children of collapsed actions.
+fn visible_actions<'a>(
+    actions: &'a [Action],
+    expanded: &HashSet<Vec<usize>>,
+    parent: &[usize],
+    output: &mut Vec<ActionNode<'a>>,
+) {
+    for (index, action) in actions.iter().enumerate() {
+        let mut path = parent.to_vec();
+        path.push(index);
+
+        let is_expanded = expanded.contains(&path);
+        output.push(ActionNode {
+            path: path.clone(),
+            depth: parent.len(),
+            action,
+        });
+
+        if is_expanded {
+            visible_actions(action.nested_actions(), expanded, &path, output);
+        }
+    }
 }
 
 pub enum Choice<'a> {
@@ -31,9 +76,28 @@ pub enum Choice<'a> {
     Quit,
 }
 
+/// Raw mode and alternate screen, restored when dropped, even on errors and panics.
+pub(crate) struct TerminalGuard;
+
+impl TerminalGuard {
+    pub(crate) fn enter() -> Result<Self> {
+        enable_raw_mode()?;
+        let guard = Self;
+        stdout().execute(EnterAlternateScreen)?;
+
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = stdout().execute(LeaveAlternateScreen);
+    }
+}
+
 pub fn run_loop(configuration: &Configuration) -> Result<Choice> {
-    enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?;
+    let _guard = TerminalGuard::enter()?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
 
     let indexed_environments: Vec<_> = configuration
@@ -48,19 +112,21 @@ pub fn run_loop(configuration: &Configuration) -> Result<Choice> {
 
     let mut state = State {
         environments: StatefulList::new(indexed_environment_names),
+        actions_focused: false,
+        action_cursor: 0,
+        expanded_actions: HashSet::new(),
     };
 
     state.environments.select_first_if_exists();
 
+    // Actions are shown with the configuration variables filled in.
+    let variables = configuration.variables.for_display();
     let mut choice = Choice::Continue;
     while matches!(choice, Choice::Continue) {
-        terminal.draw(|frame| ui(&indexed_environments, frame, &mut state))?;
+        terminal.draw(|frame| ui(&indexed_environments, &variables, frame, &mut state))?;
         choice = handle_events(&indexed_environments, &mut state)?;
     }
 
-    disable_raw_mode()?;
-    stdout().execute(LeaveAlternateScreen)?;
-
     Ok(choice)
 }
 
@@ -71,9 +137,27 @@ fn handle_events<'a>(
     if event::poll(std::time::Duration::from_millis(50))? {
         if let Event::Key(key) = event::read()? {
             if key.kind == event::KeyEventKind::Press {
+                if state.actions_focused {
+                    handle_actions_events(environments, state, key.code);
+                    return Ok(match key.code {
+                        KeyCode::Char('q') => Choice::Quit,
+                        KeyCode::Char('e') => Choice::Edit,
+                        _ => Choice::Continue,
+                    });
+                }
+
                 match key.code {
-                    KeyCode::Up => state.environments.select_previous(),
-                    KeyCode::Down => state.environments.select_next(),
+                    KeyCode::Up => {
+                        state.environments.select_previous();
+                        state.reset_actions();
+                    }
+                    KeyCode::Down => {
+                        state.environments.select_next();
+                        state.reset_actions();
+                    }
+                    KeyCode::Tab | KeyCode::Right => {
+                        state.actions_focused = state.environments.selected_index().is_some();
+                    }
                     KeyCode::Enter => {
                         if let Some(selected) = state.environments.selected_index() {
                             // Validate environment!
@@ -92,7 +176,49 @@ fn handle_events<'a>(
     Ok(Choice::Continue)
 }
 
-fn ui<'a>(environments: &[(&'a String, &'a Environment)], frame: &mut Frame, state: &mut State) {
+fn handle_actions_events(
+    environments: &[(&String, &Environment)],
+    state: &mut State,
+    code: KeyCode,
+) {
+    let Some(selected) = state.environments.selected_index() else {
+        return;
+    };
+
+    let (_, environment) = environments[selected];
+    let mut nodes = vec![];
+    visible_actions(
+        &environment.actions,
+        &state.expanded_actions,
+        &[],
+        &mut nodes,
+    );
+
+    match code {
+        KeyCode::Up => state.action_cursor = state.action_cursor.saturating_sub(1),
+        KeyCode::Down => {
+            state.action_cursor = (state.action_cursor + 1).min(nodes.len().saturating_sub(1))
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            if let Some(node) = nodes.get(state.action_cursor) {
+                if !node.action.nested_actions().is_empty()
+                    && !state.expanded_actions.remove(&node.path)
+                {
+                    state.expanded_actions.insert(node.path.clone());
+                }
+            }
+        }
+        KeyCode::Tab | KeyCode::Left | KeyCode::Esc => state.actions_focused = false,
+        _ => (),
+    }
+}
+
+fn ui<'a>(
+    environments: &[(&'a String, &'a Environment)],
+    variables: &Context,
+    frame: &mut Frame,
+    state: &mut State,
+) {
     let main_areas = Layout::new(
         Direction::Vertical,
         [Constraint::Min(0), Constraint::Length(1)],
@@ -128,8 +254,34 @@ fn ui<'a>(environments: &[(&'a String, &'a Environment)], frame: &mut Frame, sta
 
     if let Some(value) = state.environments.selected_index() {
         let (_, env) = environments.get(value).unwrap();
-        let actions: Vec<_> = env.actions.iter().map(|a| a.to_pretty_string()).collect();
-        let text = actions.iter().map(|a| format!("- {a}")).join("\n");
+        let mut nodes = vec![];
+        visible_actions(&env.actions, &state.expanded_actions, &[], &mut nodes);
+
+        let text: Vec<_> = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| {
+                let marker = match node.action.nested_actions() {
+                    [] => "-",
+                    _ if state.expanded_actions.contains(&node.path) => "▾",
+                    _ => "▸",
+                };
+                let text = format!(
+                    "{}{marker} {}",
+                    "  ".repeat(node.depth),
+                    node.action
+                        .resolved(variables)
+                        .to_pretty_string()
+                        .trim_end()
+                );
+
+                if state.actions_focused && index == state.action_cursor {
+                    Line::styled(text, Style::new().add_modifier(Modifier::REVERSED))
+                } else {
+                    Line::from(text)
+                }
+            })
+            .collect();
 
         let paragraph = Paragraph::new(text)
             .block(
@@ -138,12 +290,16 @@ fn ui<'a>(environments: &[(&'a String, &'a Environment)], frame: &mut Frame, sta
                     .borders(Borders::ALL)
                     .padding(Padding::uniform(1)),
             )
-            .wrap(Wrap { trim: true });
+            .wrap(Wrap { trim: false });
 
         frame.render_widget(paragraph, center_areas[1]);
     }
 
-    let status = Paragraph::new("UP/DOWN - Move cursor    ENTER - Select    E - Edit    Q - Quit")
-        .alignment(Alignment::Center);
+    let help = if state.actions_focused {
+        "UP/DOWN - Move cursor    ENTER/SPACE - Expand/collapse    TAB/LEFT - Back    E - Edit    Q - Quit"
+    } else {
+        "UP/DOWN - Move cursor    ENTER - Select    TAB/RIGHT - Browse actions    E - Edit    Q - Quit"
+    };
+    let status = Paragraph::new(help).alignment(Alignment::Center);
     frame.render_widget(status, main_areas[1]);
 }
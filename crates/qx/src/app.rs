@@ -1,12 +1,17 @@
-use std::path::PathBuf;
+use std::{io::Read, path::PathBuf, time::Duration};
 
 use clap::{CommandFactory, Parser};
 use color_eyre::{owo_colors::OwoColorize, Result};
 use itertools::Itertools;
-use qx_core::{banner, ActionContext, CommandExecutor, Configuration, Environment};
-use qx_storage::{ConfigurationStorage, FileAccess};
-use qx_tui::Choice;
-use tracing::level_filters::LevelFilter;
+use qx_core::{
+    banner, Action, ActionContext, BackgroundTask, Clock, CommandExecutor, Configuration, Console,
+    Context, Environment, ProcessMatcher, ResolvableClone, ShellTarget,
+};
+use qx_storage::{
+    BootEntry, BootHistory, ConfigurationStorage, FileAccess, ProcessEntry, ProcessRegistry,
+};
+use qx_tui::{Choice, TuiConsole};
+use tracing::{level_filters::LevelFilter, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 use crate::args::{Args, ArgsCommand};
@@ -27,20 +32,39 @@ impl AppStatusCode {
     }
 }
 
-pub struct App<'a, E: CommandExecutor> {
+pub struct App<'a, E: CommandExecutor, F: FileAccess, C: Console, K: Clock> {
     executor: &'a E,
+    file_access: &'a F,
+    console: &'a C,
+    clock: &'a K,
     configuration: Configuration,
     configuration_path: PathBuf,
+    shell_target: Option<ShellTarget>,
 }
 
-impl<'a, E: CommandExecutor> App<'a, E> {
-    pub fn run<F: FileAccess>(executor: &'a E, file_access: &'a F) -> Result<AppStatusCode> {
+impl<'a, E: CommandExecutor, F: FileAccess, C: Console, K: Clock> App<'a, E, F, C, K> {
+    pub fn run(
+        executor: &'a E,
+        file_access: &'a F,
+        console: &'a C,
+        clock: &'a K,
+    ) -> Result<AppStatusCode> {
         Self::setup_error_handling()?;
-        Self::show_banner();
 
         let args = Args::parse();
         Self::setup_logging(args.verbose);
 
+        // The shell integration script is meant to be evaluated, print it alone.
+        if let ArgsCommand::Init(shell) = args.command() {
+            print!("{}", shell.init_script());
+            return Ok(AppStatusCode::Success);
+        }
+
+        // Read before anything is started, so the wrapper variables do not reach child processes.
+        let shell_target = ShellTarget::take_from_env()?;
+
+        Self::show_banner();
+
         let storage = ConfigurationStorage::new(file_access);
 
         // Use a loop to handle "hot" reloading of the configuration
@@ -52,13 +76,22 @@ impl<'a, E: CommandExecutor> App<'a, E> {
             let app = Self {
                 configuration,
                 configuration_path,
+                shell_target: shell_target.clone(),
                 executor,
+                file_access,
+                console,
+                clock,
             };
 
             let result = match args.command() {
                 ArgsCommand::Interactive => app.handle_interactive(),
-                ArgsCommand::Boot(filter) => app.handle_environment(filter),
+                ArgsCommand::Boot(filter) => app.handle_environment(filter, false),
+                ArgsCommand::DryRun(filter) => app.handle_environment(filter, true),
                 ArgsCommand::Edit => app.handle_edit(),
+                ArgsCommand::ListProcesses => app.handle_list_processes(),
+                ArgsCommand::StopProcess(pid) => app.handle_stop_process(pid),
+                ArgsCommand::Background => app.handle_background(),
+                ArgsCommand::Init(_) => unreachable!("handled before loading the configuration"),
             }?;
 
             match result {
@@ -93,17 +126,61 @@ impl<'a, E: CommandExecutor> App<'a, E> {
         println!("{}", banner());
     }
 
-    fn boot(&self, environment: &Environment) -> Result<()> {
+    fn boot<D: Console>(&self, environment: &Environment, console: &D) -> Result<()> {
         println!("  > Booting environment: {}", environment.name);
         println!();
 
-        let context = ActionContext {
-            system: &self.configuration.system,
-            context: &self.configuration.variables,
-            executor: self.executor,
-        };
+        let booted_at = self
+            .clock
+            .now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        let mut context = ActionContext::new(
+            &self.configuration.system,
+            self.configuration.variables.clone(),
+            self.executor,
+            self.file_access,
+            console,
+            self.clock,
+        );
+        let result = context
+            .load_dotenv_files(&self.configuration.dotenv, self.configuration.export_dotenv)
+            .and_then(|_| environment.boot(&mut context))
+            .and_then(|_| self.apply_shell_changes(environment, &context.context));
+
+        // Failing to save the history must not hide the boot result.
+        if let Err(error) = BootHistory::new(self.file_access).record(BootEntry {
+            environment: environment.name.clone(),
+            booted_at,
+            success: result.is_ok(),
+            checklists: context.checklists,
+        }) {
+            warn!(
+                message = "Could not record the boot history",
+                error = %error
+            );
+        }
 
-        environment.boot(&context)
+        result
+    }
+
+    fn apply_shell_changes(&self, environment: &Environment, context: &Context) -> Result<()> {
+        let changes = environment.shell_changes(context);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        match &self.shell_target {
+            Some(target) => target.write(self.file_access, &changes)?,
+            None => println!(
+                "{}",
+                "  > Run 'qx init <shell>' to let qx change the directory and environment of your shell"
+                    .yellow()
+            ),
+        }
+
+        Ok(())
     }
 
     fn edit(&self) -> Result<()> {
@@ -122,13 +199,15 @@ impl<'a, E: CommandExecutor> App<'a, E> {
         Ok(())
     }
 
-    fn handle_environment(&self, filter: Option<&String>) -> Result<AppStatusCode> {
+    fn handle_environment(&self, filter: Option<&String>, dry_run: bool) -> Result<AppStatusCode> {
         if let Some(filter) = filter {
             let filtered_environments = self.configuration.filter_environments(filter);
             if filtered_environments.is_empty() {
                 self.handle_environment_no_match(filter)
             } else if filtered_environments.len() > 1 {
                 self.handle_environment_too_many_matches(&filtered_environments, filter)
+            } else if dry_run {
+                self.handle_dry_run(filtered_environments[0])
             } else {
                 self.handle_boot(filtered_environments[0])
             }
@@ -183,7 +262,12 @@ impl<'a, E: CommandExecutor> App<'a, E> {
 
         let choice = qx_tui::run_loop(&self.configuration)?;
         match choice {
-            Choice::Boot(env) => self.boot(env)?,
+            Choice::Boot(env) => {
+                let console = TuiConsole::default();
+                let result = self.boot(env, &console);
+                console.finish()?;
+                result?
+            }
             Choice::Edit => {
                 self.edit()?;
                 return Ok(AppStatusCode::Restart);
@@ -195,11 +279,47 @@ impl<'a, E: CommandExecutor> App<'a, E> {
     }
 
     fn handle_boot(&self, environment: &Environment) -> Result<AppStatusCode> {
-        self.boot(environment)?;
+        self.boot(environment, self.console)?;
 
         Ok(AppStatusCode::Success)
     }
 
+    fn handle_dry_run(&self, environment: &Environment) -> Result<AppStatusCode> {
+        println!(
+            "{}",
+            format!("Actions for {environment}:").underline().bold()
+        );
+        Self::print_actions(
+            &environment.actions,
+            &self.configuration.variables.for_display(),
+            1,
+        );
+
+        environment.validate_exports()?;
+        let changes = environment.shell_changes(&self.configuration.variables.for_display());
+        if let Some(directory) = &changes.working_directory {
+            println!("  - Change the shell directory to {directory:?}");
+        }
+        for (name, value) in &changes.environment {
+            println!("  - Export {name}={value:?} to the shell");
+        }
+        println!();
+
+        Ok(AppStatusCode::Success)
+    }
+
+    fn print_actions(actions: &[Action], variables: &Context, depth: usize) {
+        for action in actions {
+            let action = action.resolved(variables);
+            println!(
+                "{}- {}",
+                "  ".repeat(depth),
+                action.to_pretty_string().trim_end()
+            );
+            Self::print_actions(action.nested_actions(), variables, depth + 1);
+        }
+    }
+
     fn handle_list_environments(&self) -> Result<AppStatusCode> {
         let envs = self
             .configuration
@@ -218,4 +338,109 @@ impl<'a, E: CommandExecutor> App<'a, E> {
 
         Ok(AppStatusCode::Error)
     }
+
+    fn list_running_processes(&self) -> Result<Vec<ProcessEntry>> {
+        let registry = ProcessRegistry::new(self.file_access, self.clock);
+        let entries = registry.list()?;
+
+        // Forget about processes which are not running anymore.
+        let matcher = ProcessMatcher {
+            pids: Some(entries.iter().map(|e| e.pid).collect()),
+            ..Default::default()
+        };
+        let running_pids: Vec<_> =
+            qx_core::find_processes(&self.configuration.system, self.executor, &matcher)?
+                .into_iter()
+                .map(|p| p.pid)
+                .collect();
+
+        let (running, stopped): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|e| running_pids.contains(&e.pid));
+        if !stopped.is_empty() {
+            registry.forget(&stopped.iter().map(|e| e.pid).collect::<Vec<_>>())?;
+        }
+
+        Ok(running)
+    }
+
+    fn handle_list_processes(&self) -> Result<AppStatusCode> {
+        let processes = self.list_running_processes()?;
+        if processes.is_empty() {
+            println!("  > No background process is running");
+            println!();
+            return Ok(AppStatusCode::Success);
+        }
+
+        println!("{}", "Background processes:".underline().bold());
+        for process in processes {
+            println!(
+                "  - [{}] {}{}",
+                process.pid,
+                process.description,
+                process
+                    .environment
+                    .map(|e| format!(" (environment: {e})"))
+                    .unwrap_or_default()
+            );
+        }
+        println!();
+
+        Ok(AppStatusCode::Success)
+    }
+
+    fn handle_stop_process(&self, pid: u32) -> Result<AppStatusCode> {
+        let processes = self.list_running_processes()?;
+        if !processes.iter().any(|p| p.pid == pid) {
+            eprintln!(
+                "{}",
+                format!("Error: no background process found with PID {pid}").red()
+            );
+
+            return Ok(AppStatusCode::Error);
+        }
+
+        println!("  > Stopping background process {pid}");
+        println!();
+
+        let matcher = ProcessMatcher {
+            pids: Some(vec![pid]),
+            ..Default::default()
+        };
+        qx_core::kill_processes(
+            &self.configuration.system,
+            self.executor,
+            self.clock,
+            &matcher,
+            Duration::from_secs(5),
+        )?;
+        ProcessRegistry::new(self.file_access, self.clock).unregister(pid)?;
+
+        Ok(AppStatusCode::Success)
+    }
+
+    fn handle_background(&self) -> Result<AppStatusCode> {
+        let mut data = String::new();
+        std::io::stdin().read_to_string(&mut data)?;
+        let task = BackgroundTask::from_yaml(&data)?;
+
+        let registry = ProcessRegistry::new(self.file_access, self.clock);
+        let pid = std::process::id();
+        registry.register(ProcessEntry {
+            pid,
+            environment: task.environment.clone(),
+            description: task.description(),
+            started_at: self
+                .clock
+                .now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs(),
+        })?;
+
+        let result = task.run(self.executor, self.file_access, self.console, self.clock);
+        registry.unregister(pid)?;
+        result?;
+
+        Ok(AppStatusCode::Success)
+    }
 }
@@ -1,18 +1,24 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use color_eyre::Result;
-use qx_core::Configuration;
+use qx_core::{Configuration, ShellKind};
 use qx_storage::{ConfigurationStorage, FileAccess};
 
 pub enum ArgsCommand<'a> {
+    Init(ShellKind),
     Boot(Option<&'a String>),
+    DryRun(Option<&'a String>),
     Interactive,
     Edit,
+    ListProcesses,
+    StopProcess(u32),
+    Background,
 }
 
 #[derive(Debug, Clone, Parser)]
 #[clap(author)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
     /// Path to the configuration file to use
     #[arg(short, long)]
@@ -30,8 +36,33 @@ pub struct Args {
     #[arg(short, long)]
     pub verbose: bool,
 
+    /// Show the actions of an environment without running them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// List background processes started by qx
+    #[arg(long)]
+    pub processes: bool,
+
+    /// Stop a background process started by qx
+    #[arg(long, value_name = "PID")]
+    pub stop: Option<u32>,
+
+    /// Run a background task read from the standard input
+    #[arg(long, hide = true)]
+    pub background: bool,
+
     /// Environment match
     pub environment: Option<String>,
+
+    #[command(subcommand)]
+    pub subcommand: Option<ArgsSubcommand>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ArgsSubcommand {
+    /// Print the shell integration script (bash, zsh, fish or pwsh)
+    Init { shell: ShellKind },
 }
 
 impl Args {
@@ -65,10 +96,20 @@ impl Args {
     }
 
     pub fn command(&self) -> ArgsCommand {
-        if self.edit {
+        if let Some(ArgsSubcommand::Init { shell }) = &self.subcommand {
+            ArgsCommand::Init(*shell)
+        } else if self.background {
+            ArgsCommand::Background
+        } else if self.processes {
+            ArgsCommand::ListProcesses
+        } else if let Some(pid) = self.stop {
+            ArgsCommand::StopProcess(pid)
+        } else if self.edit {
             ArgsCommand::Edit
         } else if self.interactive {
             ArgsCommand::Interactive
+        } else if self.dry_run {
+            ArgsCommand::DryRun(self.environment.as_ref())
         } else {
             ArgsCommand::Boot(self.environment.as_ref())
         }
@@ -3,13 +3,15 @@ mod args;
 
 use app::App;
 use color_eyre::Result;
-use qx_core::CommandExecutorIo;
+use qx_core::{ClockIo, CommandExecutorIo, ConsoleIo};
 use qx_storage::FileAccessIo;
 
 fn main() -> Result<()> {
     let executor_io = CommandExecutorIo::default();
     let file_access_io = FileAccessIo::default();
+    let console_io = ConsoleIo::default();
+    let clock_io = ClockIo::default();
 
-    let result = App::run(&executor_io, &file_access_io)?;
+    let result = App::run(&executor_io, &file_access_io, &console_io, &clock_io)?;
     std::process::exit(result.as_code() as i32);
 }